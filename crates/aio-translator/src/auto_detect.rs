@@ -0,0 +1,243 @@
+use aio_translator_interface::{
+    AsyncTranslator, Detector, Language, TranslationListOutput, TranslationOutput, chunk::Limits,
+    error::Error, prompt::PromptBuilder,
+};
+use async_trait::async_trait;
+
+/// Fills in `from` with a [`Detector`] for translators that need the source language
+pub struct AutoDetect<T: AsyncTranslator, D: Detector> {
+    t: T,
+    detector: D,
+    /// Used when the detector gives up, e.g. for numbers or very short text
+    fallback: Option<Language>,
+}
+
+impl<T: AsyncTranslator, D: Detector> AutoDetect<T, D> {
+    /// Create a new AutoDetect wrapper
+    /// - `detector`: used when `from` is `None`
+    /// - `fallback`: source language for text the detector can't classify, `None` passes it on undetected
+    pub fn new(t: T, detector: D, fallback: Option<Language>) -> Self {
+        Self {
+            t,
+            detector,
+            fallback,
+        }
+    }
+
+    /// Detected language of `text` or the fallback
+    pub fn detect(&self, text: &str) -> Option<Language> {
+        self.detector.detect_language(text).or(self.fallback)
+    }
+
+    /// Translates and returns the source language of each segment.
+    /// Segments are grouped by detected language and each group is sent as its own batch.
+    pub async fn translate_vec_detected(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<(TranslationListOutput, Vec<Option<Language>>)> {
+        if from.is_some() {
+            let trans = self.t.translate_vec(query, context, from, to).await?;
+            return Ok((trans, vec![from; query.len()]));
+        }
+
+        let detected = query.iter().map(|q| self.detect(q)).collect::<Vec<_>>();
+        // groups in order of first appearance
+        let mut groups: Vec<(Option<Language>, Vec<usize>)> = vec![];
+        for (i, lang) in detected.iter().enumerate() {
+            match groups.iter_mut().find(|(l, _)| l == lang) {
+                Some((_, indices)) => indices.push(i),
+                None => groups.push((*lang, vec![i])),
+            }
+        }
+
+        let mut text = vec![String::new(); query.len()];
+        for (lang, indices) in &groups {
+            let batch = indices
+                .iter()
+                .map(|&i| query[i].clone())
+                .collect::<Vec<_>>();
+            let trans = self
+                .t
+                .translate_vec(&batch, context.clone(), *lang, to)
+                .await?;
+            if trans.text.len() != batch.len() {
+                return Err(Error::NoResponse.into());
+            }
+            for (&i, t) in indices.iter().zip(trans.text) {
+                text[i] = t;
+            }
+        }
+        let lang = match groups.as_slice() {
+            [(lang, _)] => *lang,
+            _ => None,
+        };
+        Ok((TranslationListOutput { text, lang }, detected))
+    }
+}
+
+#[async_trait]
+impl<T: AsyncTranslator + Send + Sync, D: Detector + Send + Sync> AsyncTranslator
+    for AutoDetect<T, D>
+{
+    fn local(&self) -> bool {
+        self.t.local()
+    }
+
+    fn limits(&self) -> Limits {
+        self.t.limits()
+    }
+
+    /// Without a source language or fallback the detected language decides, so only the target is known
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        match from.or(self.fallback) {
+            Some(from) => self.t.supports(Some(from), to),
+            None => true,
+        }
+    }
+
+    async fn translate(
+        &self,
+        query: &str,
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        let from = from.or_else(|| self.detect(query));
+        let mut trans = self.t.translate(query, context, from, to).await?;
+        trans.lang = trans.lang.or(from);
+        Ok(trans)
+    }
+
+    /// [`TranslationListOutput::lang`] is only set if all segments share one language,
+    /// use [`AutoDetect::translate_vec_detected`] for the language of each segment
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        Ok(self
+            .translate_vec_detected(query, context, from, to)
+            .await?
+            .0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Detects Japanese if there is any kana, English for ascii letters
+    struct Kana;
+
+    impl Detector for Kana {
+        fn detect_language(&self, text: &str) -> Option<Language> {
+            if text.chars().any(|c| ('\u{3040}'..='\u{30ff}').contains(&c)) {
+                Some(Language::Japanese)
+            } else if text.chars().any(|c| c.is_ascii_alphabetic()) {
+                Some(Language::English)
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Fails without a source language and tags each segment with it
+    struct NeedsFrom;
+
+    #[async_trait]
+    impl AsyncTranslator for NeedsFrom {
+        fn local(&self) -> bool {
+            true
+        }
+
+        async fn translate(
+            &self,
+            query: &str,
+            context: Option<PromptBuilder>,
+            from: Option<Language>,
+            to: &Language,
+        ) -> anyhow::Result<TranslationOutput> {
+            let mut out = self
+                .translate_vec(&[query.to_owned()], context, from, to)
+                .await?;
+            Ok(TranslationOutput {
+                text: out.text.remove(0),
+                lang: out.lang,
+            })
+        }
+
+        async fn translate_vec(
+            &self,
+            query: &[String],
+            _: Option<PromptBuilder>,
+            from: Option<Language>,
+            _: &Language,
+        ) -> anyhow::Result<TranslationListOutput> {
+            let from = from.ok_or(Error::NoLanguage)?;
+            Ok(TranslationListOutput {
+                text: query
+                    .iter()
+                    .map(|q| format!("{}:{q}", from.to_639_1().unwrap_or_default()))
+                    .collect(),
+                lang: Some(from),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn group_by_language() {
+        let t = AutoDetect::new(NeedsFrom, Kana, Some(Language::English));
+        let query = ["こんにちは", "hello", "123", "さようなら"]
+            .map(String::from)
+            .to_vec();
+        let (out, detected) = t
+            .translate_vec_detected(&query, None, None, &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(
+            out.text,
+            vec!["ja:こんにちは", "en:hello", "en:123", "ja:さようなら"]
+        );
+        assert_eq!(out.lang, None);
+        assert_eq!(
+            detected,
+            vec![
+                Some(Language::Japanese),
+                Some(Language::English),
+                Some(Language::English),
+                Some(Language::Japanese)
+            ]
+        );
+
+        let out = t
+            .translate("こんにちは", None, None, &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(out.lang, Some(Language::Japanese));
+    }
+
+    #[tokio::test]
+    async fn keep_explicit_source() {
+        let t = AutoDetect::new(NeedsFrom, Kana, None);
+        let out = t
+            .translate_vec(
+                &["hello".to_owned()],
+                None,
+                Some(Language::French),
+                &Language::German,
+            )
+            .await
+            .unwrap();
+        assert_eq!(out.text, vec!["fr:hello"]);
+        assert!(
+            t.translate("123", None, None, &Language::German)
+                .await
+                .is_err()
+        );
+    }
+}
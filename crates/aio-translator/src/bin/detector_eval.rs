@@ -0,0 +1,38 @@
+//! Compares the detectors on a labeled corpus, see [`aio_translator::detector_eval`].
+//!
+//! Usage: `detector_eval <corpus.tsv>` with one `language<TAB>text` sample per line.
+//! Enable the `whatlang` and `lingua` features to include those detectors.
+
+use aio_translator::{
+    CjkDetector, Detector, LangIdDetector,
+    detector_eval::{comparison_markdown, evaluate_detector, load_labeled_tsv},
+};
+
+fn main() -> anyhow::Result<()> {
+    let Some(path) = std::env::args().nth(1) else {
+        anyhow::bail!("usage: detector_eval <corpus.tsv>");
+    };
+    let samples = load_labeled_tsv(path)?;
+
+    let detectors: Vec<(&str, Box<dyn Detector>)> = vec![
+        ("langid", Box::new(LangIdDetector::new()?)),
+        ("cjk", Box::new(CjkDetector::standalone())),
+        #[cfg(feature = "whatlang")]
+        (
+            "whatlang",
+            Box::new(aio_translator::WhatLangDetector::new()),
+        ),
+        #[cfg(feature = "lingua")]
+        ("lingua", Box::new(aio_translator::LinguaDetector::new())),
+    ];
+
+    let reports = detectors
+        .iter()
+        .map(|(name, detector)| evaluate_detector(*name, detector.as_ref(), &samples))
+        .collect::<Vec<_>>();
+    println!("# Detectors\n\n{}", comparison_markdown(&reports));
+    for report in &reports {
+        println!("{}", report.to_markdown());
+    }
+    Ok(())
+}
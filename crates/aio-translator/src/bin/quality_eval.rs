@@ -0,0 +1,107 @@
+//! Compares translators on a parallel test set, see [`aio_translator::quality_eval`].
+//!
+//! Usage: `quality_eval <from|auto> <to> <test.tsv | source.txt reference.txt> --backend <name>... [--batch <n>] [--json <path>]`
+//! with one `source<TAB>reference` pair per line of the TSV. Languages are tags like `ja` or `zh-Hant`.
+//! Google and DeepL take their keys from `GOOGLE_API_KEY` and `DEEPL_API_KEY`.
+
+use aio_translator::{
+    AsyncTranslator, ComputeType, DeeplTranslator, GoogleTranslator, JParaCrawlSize,
+    JParaCrawlTranslator, Language, M2M100Size, M2M100Translator, MBart50Translator,
+    MyMemoryTranslator, NLLBSize, NLLBTranslator, NoneTranslator, OriginalTranslator,
+    PapagoTranslator, SugoiTranslator,
+    quality_eval::{
+        comparison_json, comparison_markdown, evaluate_translator, load_parallel_files,
+        load_parallel_tsv,
+    },
+    wrapper::Chunker,
+};
+
+const USAGE: &str = "usage: quality_eval <from|auto> <to> <test.tsv | source.txt reference.txt> --backend <name>... [--batch <n>] [--json <path>]";
+
+fn language(tag: &str) -> anyhow::Result<Language> {
+    Language::from_tag(tag).ok_or_else(|| anyhow::anyhow!("unknown language {tag}"))
+}
+
+async fn backend(name: &str) -> anyhow::Result<Box<dyn AsyncTranslator>> {
+    let env =
+        |key: &str| std::env::var(key).map_err(|_| anyhow::anyhow!("{name} needs {key} to be set"));
+    Ok(match name {
+        "original" => Box::new(OriginalTranslator::new()),
+        "none" => Box::new(NoneTranslator::new()),
+        "sugoi" => Box::new(Chunker::new(SugoiTranslator::new(
+            false,
+            ComputeType::DEFAULT,
+        ))),
+        "nllb" => Box::new(Chunker::new(NLLBTranslator::new(
+            false,
+            ComputeType::DEFAULT,
+            NLLBSize::SmallDistilled,
+        ))),
+        "m2m100" => Box::new(Chunker::new(M2M100Translator::new(
+            false,
+            ComputeType::DEFAULT,
+            M2M100Size::Small,
+        ))),
+        "mbart50" => Box::new(Chunker::new(MBart50Translator::new(
+            false,
+            ComputeType::DEFAULT,
+        ))),
+        "jparacrawl" => Box::new(Chunker::new(JParaCrawlTranslator::new(
+            false,
+            false,
+            ComputeType::DEFAULT,
+            JParaCrawlSize::Base,
+        ))),
+        "mymemory" => Box::new(Chunker::new(MyMemoryTranslator::new())),
+        "papago" => Box::new(Chunker::new(PapagoTranslator::new(false).await?)),
+        "google" => Box::new(Chunker::new(GoogleTranslator::new(env("GOOGLE_API_KEY")?))),
+        "deepl" => Box::new(Chunker::new(DeeplTranslator::new(env("DEEPL_API_KEY")?))),
+        _ => anyhow::bail!("unknown backend {name}"),
+    })
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    let mut files = vec![];
+    let mut backends = vec![];
+    let mut batch = 16;
+    let mut json = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("{arg} needs a value"))
+        };
+        match arg.as_str() {
+            "--backend" => backends.push(value()?),
+            "--batch" => batch = value()?.parse()?,
+            "--json" => json = Some(value()?),
+            _ => files.push(arg),
+        }
+    }
+    let (from, to, samples) = match files.as_slice() {
+        [from, to, test_set] => (from, to, load_parallel_tsv(test_set)?),
+        [from, to, source, reference] => (from, to, load_parallel_files(source, reference)?),
+        _ => anyhow::bail!(USAGE),
+    };
+    let from = match from.as_str() {
+        "auto" => None,
+        tag => Some(language(tag)?),
+    };
+    let to = language(to)?;
+    if backends.is_empty() {
+        anyhow::bail!(USAGE);
+    }
+
+    let mut reports = vec![];
+    for name in &backends {
+        let t = backend(name).await?;
+        reports
+            .push(evaluate_translator(name.as_str(), t.as_ref(), &samples, from, &to, batch).await);
+    }
+    println!("# Translators\n\n{}", comparison_markdown(&reports));
+    if let Some(path) = json {
+        std::fs::write(path, comparison_json(&reports)?)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use aio_translator_interface::{
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput,
+    chunk::{Chunks, Limits},
+    error::Error,
+    prompt::PromptBuilder,
+};
+use async_trait::async_trait;
+
+/// Splits long segments and oversized batches to fit the [`Limits`] of the translator
+pub struct Chunker<T: AsyncTranslator> {
+    t: T,
+    limits: Limits,
+}
+
+impl<T: AsyncTranslator> Chunker<T> {
+    /// Create a new Chunker wrapper using the limits declared by the translator
+    pub fn new(t: T) -> Self {
+        let limits = t.limits();
+        Self { t, limits }
+    }
+
+    /// Create a new Chunker wrapper with custom limits
+    pub fn with_limits(t: T, limits: Limits) -> Self {
+        Self { t, limits }
+    }
+}
+
+#[async_trait]
+impl<T: AsyncTranslator + Send + Sync> AsyncTranslator for Chunker<T> {
+    fn local(&self) -> bool {
+        self.t.local()
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        self.t.supports(from, to)
+    }
+
+    async fn translate(
+        &self,
+        query: &str,
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        let chunks = Chunks::new(&[query.to_owned()], &self.limits);
+        if let [piece] = chunks.pieces() {
+            return self.t.translate(piece, context, from, to).await;
+        }
+        let mut trans = self
+            .translate_vec(&[query.to_owned()], context, from, to)
+            .await?;
+        Ok(TranslationOutput {
+            text: trans.text.remove(0),
+            lang: trans.lang,
+        })
+    }
+
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        if self.limits.is_unlimited() {
+            return self.t.translate_vec(query, context, from, to).await;
+        }
+        let chunks = Chunks::new(query, &self.limits);
+        let mut text = Vec::with_capacity(chunks.pieces().len());
+        let mut lang = None;
+        for batch in chunks.batches() {
+            let trans = self
+                .t
+                .translate_vec(batch, context.clone(), from, to)
+                .await?;
+            lang = lang.or(trans.lang);
+            text.extend(trans.text);
+        }
+        Ok(TranslationListOutput {
+            text: chunks.stitch(text).ok_or(Error::NoResponse)?,
+            lang,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use aio_translator_interface::chunk::LengthUnit;
+
+    use super::*;
+
+    /// Rejects requests over 20 bytes like MyMemory and records the batches
+    #[derive(Default)]
+    struct Small {
+        batches: Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl AsyncTranslator for Small {
+        fn local(&self) -> bool {
+            true
+        }
+
+        fn limits(&self) -> Limits {
+            Limits {
+                unit: LengthUnit::Bytes,
+                max_request_len: Some(20),
+                max_segments: Some(2),
+                ..Default::default()
+            }
+        }
+
+        async fn translate(
+            &self,
+            query: &str,
+            context: Option<PromptBuilder>,
+            from: Option<Language>,
+            to: &Language,
+        ) -> anyhow::Result<TranslationOutput> {
+            let mut out = self
+                .translate_vec(&[query.to_owned()], context, from, to)
+                .await?;
+            Ok(TranslationOutput {
+                text: out.text.remove(0),
+                lang: out.lang,
+            })
+        }
+
+        async fn translate_vec(
+            &self,
+            query: &[String],
+            _: Option<PromptBuilder>,
+            from: Option<Language>,
+            _: &Language,
+        ) -> anyhow::Result<TranslationListOutput> {
+            let len = query.iter().map(|v| v.len()).sum::<usize>();
+            if len > 20 {
+                return Err(Error::RequestToLong(len as u32, 20).into());
+            }
+            self.batches.lock().unwrap().push(query.len());
+            Ok(TranslationListOutput {
+                text: query.iter().map(|v| v.to_uppercase()).collect(),
+                lang: from,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn stitch_pieces() {
+        let t = Chunker::new(Small::default());
+        let out = t
+            .translate(
+                "This is long. It has three sentences. Really.",
+                None,
+                None,
+                &Language::German,
+            )
+            .await
+            .unwrap();
+        assert_eq!(out.text, "THIS IS LONG. IT HAS THREE SENTENCES. REALLY.");
+
+        let query = ["a", "b", "c"].map(String::from).to_vec();
+        let out = t
+            .translate_vec(&query, None, None, &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(out.text, vec!["A", "B", "C"]);
+        assert_eq!(*t.t.batches.lock().unwrap().last().unwrap(), 1);
+    }
+}
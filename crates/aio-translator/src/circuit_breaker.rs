@@ -0,0 +1,266 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use aio_translator_interface::{
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, chunk::Limits,
+    error::Error, prompt::PromptBuilder,
+};
+use async_trait::async_trait;
+
+/// State of a [`CircuitBreaker`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests go through
+    Closed,
+    /// Requests fail fast with [`Error::CircuitOpen`]
+    Open,
+    /// The cooldown is over, the next request probes whether the translator recovered
+    HalfOpen,
+}
+
+enum Inner {
+    Closed { failures: VecDeque<Instant> },
+    Open { until: Instant },
+    HalfOpen { probing: bool },
+}
+
+/// Stops calling a translator that keeps failing, so callers can move on to another one right away
+pub struct CircuitBreaker<T: AsyncTranslator> {
+    t: T,
+    inner: Mutex<Inner>,
+    threshold: usize,
+    window: Duration,
+    cooldown: Duration,
+}
+
+impl<T: AsyncTranslator> CircuitBreaker<T> {
+    /// Create a new CircuitBreaker wrapper
+    /// - `threshold`: failures within `window` that open the circuit
+    /// - `window`: how long a failure counts
+    /// - `cooldown`: how long the circuit stays open before a probe request is let through
+    pub fn new(t: T, threshold: usize, window: Duration, cooldown: Duration) -> Self {
+        Self {
+            t,
+            inner: Mutex::new(Inner::Closed {
+                failures: VecDeque::new(),
+            }),
+            threshold: threshold.max(1),
+            window,
+            cooldown,
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        match &*self.inner.lock().unwrap() {
+            Inner::Closed { .. } => CircuitState::Closed,
+            Inner::Open { until } if *until > Instant::now() => CircuitState::Open,
+            Inner::Open { .. } | Inner::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Whether a request would currently be sent instead of failing fast
+    pub fn is_available(&self) -> bool {
+        match &*self.inner.lock().unwrap() {
+            Inner::Closed { .. } => true,
+            Inner::Open { until } => *until <= Instant::now(),
+            Inner::HalfOpen { probing } => !probing,
+        }
+    }
+
+    /// Lets a request through, marks it as the probe if the circuit is half open
+    fn enter(&self) -> Result<Call<'_, T>, Error> {
+        let mut inner = self.inner.lock().unwrap();
+        let probe = match &*inner {
+            Inner::Closed { .. } => false,
+            Inner::Open { until } if *until > Instant::now() => return Err(Error::CircuitOpen),
+            Inner::HalfOpen { probing: true } => return Err(Error::CircuitOpen),
+            Inner::Open { .. } | Inner::HalfOpen { .. } => true,
+        };
+        if probe {
+            *inner = Inner::HalfOpen { probing: true };
+        }
+        Ok(Call {
+            breaker: self,
+            probe,
+            done: false,
+        })
+    }
+
+    async fn call<R, F>(&self, f: F) -> anyhow::Result<R>
+    where
+        F: Future<Output = anyhow::Result<R>>,
+    {
+        let mut call = self.enter()?;
+        let r = f.await;
+        call.finish(r.as_ref().err().is_none_or(|e| !is_failure(e)));
+        r
+    }
+}
+
+/// Errors caused by the input or our own checks say nothing about the health of the translator
+fn is_failure(e: &anyhow::Error) -> bool {
+    !matches!(
+        e.downcast_ref::<Error>(),
+        Some(
+            Error::UnknownLanguage(_)
+                | Error::UnknownLanguageGroup(..)
+                | Error::NoLanguage
+                | Error::RequestToLong(..)
+                | Error::TermsLost(_)
+                | Error::PlaceholderMismatch(_)
+        )
+    )
+}
+
+/// A request in flight, releases the probe slot if the request is cancelled
+struct Call<'a, T: AsyncTranslator> {
+    breaker: &'a CircuitBreaker<T>,
+    probe: bool,
+    done: bool,
+}
+
+impl<T: AsyncTranslator> Call<'_, T> {
+    fn finish(&mut self, success: bool) {
+        self.done = true;
+        let breaker = self.breaker;
+        let now = Instant::now();
+        let mut inner = breaker.inner.lock().unwrap();
+        match (&mut *inner, success) {
+            (Inner::HalfOpen { .. }, true) if self.probe => {
+                *inner = Inner::Closed {
+                    failures: VecDeque::new(),
+                }
+            }
+            (Inner::HalfOpen { .. }, false) if self.probe => {
+                *inner = Inner::Open {
+                    until: now + breaker.cooldown,
+                }
+            }
+            (Inner::Closed { failures }, false) => {
+                failures.push_back(now);
+                while failures
+                    .front()
+                    .is_some_and(|v| now.duration_since(*v) > breaker.window)
+                {
+                    failures.pop_front();
+                }
+                if failures.len() >= breaker.threshold {
+                    *inner = Inner::Open {
+                        until: now + breaker.cooldown,
+                    };
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<T: AsyncTranslator> Drop for Call<'_, T> {
+    fn drop(&mut self) {
+        if self.probe && !self.done {
+            *self.breaker.inner.lock().unwrap() = Inner::HalfOpen { probing: false };
+        }
+    }
+}
+
+#[async_trait]
+impl<T: AsyncTranslator + Send + Sync> AsyncTranslator for CircuitBreaker<T> {
+    fn local(&self) -> bool {
+        self.t.local()
+    }
+
+    fn limits(&self) -> Limits {
+        self.t.limits()
+    }
+
+    /// Nothing is supported while the circuit is open, so a `Router` moves on to the next translator
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        self.is_available() && self.t.supports(from, to)
+    }
+
+    async fn translate(
+        &self,
+        query: &str,
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        self.call(self.t.translate(query, context, from, to)).await
+    }
+
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        self.call(self.t.translate_vec(query, context, from, to))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+    use crate::test_support::Fake;
+
+    fn is_open(r: anyhow::Result<TranslationOutput>) -> bool {
+        matches!(
+            r.err().and_then(|e| e.downcast::<Error>().ok()),
+            Some(Error::CircuitOpen)
+        )
+    }
+
+    #[tokio::test]
+    async fn open_probe_and_close() {
+        let t = CircuitBreaker::new(
+            Fake::echo(),
+            2,
+            Duration::from_secs(10),
+            Duration::from_millis(50),
+        );
+        t.t.set_failing(Some(|| Error::RequestFailed(503)));
+        for _ in 0..2 {
+            let r = t.translate("a", None, None, &Language::German).await;
+            assert!(!is_open(r));
+        }
+        assert_eq!(t.state(), CircuitState::Open);
+        assert!(!t.supports(None, &Language::German));
+        let r = t.translate("a", None, None, &Language::German).await;
+        assert!(is_open(r));
+        assert_eq!(t.t.calls.load(Ordering::SeqCst), 2);
+
+        // failed probe opens the circuit again
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(t.state(), CircuitState::HalfOpen);
+        assert!(t.is_available() && t.supports(None, &Language::German));
+        let r = t.translate("a", None, None, &Language::German).await;
+        assert!(!is_open(r));
+        assert_eq!(t.state(), CircuitState::Open);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        t.t.set_failing(None);
+        let r = t.translate("a", None, None, &Language::German).await;
+        assert_eq!(r.unwrap().text, "a");
+        assert_eq!(t.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn input_errors_do_not_count() {
+        let t = CircuitBreaker::new(
+            Fake::echo().failing(|| Error::UnknownLanguage(Language::Klingon)),
+            1,
+            Duration::from_secs(10),
+            Duration::from_secs(10),
+        );
+        let r = t.translate("a", None, None, &Language::Klingon).await;
+        assert!(r.is_err());
+        assert_eq!(t.state(), CircuitState::Closed);
+    }
+}
@@ -0,0 +1,195 @@
+use aio_translator_interface::{
+    Detector, Language,
+    detect::{is_han, is_hangul, is_kana, rank_scores},
+};
+
+/// Han characters only used in Simplified Chinese
+const SIMPLIFIED: &str = "们这个时说对发过还开关问门见长东车马鸟鱼书爱让话语读买卖钱电脑热样种经历现进运动边场从为头产业员务变认识觉欢听华军师战单难题谁请谢谈级红绿伤给论结乐吗气实广应";
+/// Han characters only used in Traditional Chinese
+const TRADITIONAL: &str =
+    "們這說來國會對發過還關讓讀寫賣錢腦樣經歷邊從產變覺歡聽戰單綠樂嗎麼裡學體點氣萬與實數廣應";
+/// Characters only used in Japanese, shinjitai and the iteration mark
+const JAPANESE: &str = "気実広応駅売図読説対発関様経歴辺従産変覚歓戦単楽緑銭脳譲県々";
+
+/// Characters of `text` per script
+#[derive(Default, Debug)]
+struct ScriptCounts {
+    kana: usize,
+    hangul: usize,
+    han: usize,
+    /// Letters of any other script
+    other: usize,
+    simplified: usize,
+    traditional: usize,
+    japanese: usize,
+}
+
+impl ScriptCounts {
+    fn of(text: &str) -> Self {
+        let mut counts = Self::default();
+        for c in text.chars() {
+            if is_kana(c) {
+                counts.kana += 1;
+            } else if is_hangul(c) {
+                counts.hangul += 1;
+            } else if is_han(c) {
+                counts.han += 1;
+            } else if c.is_alphabetic() {
+                counts.other += 1;
+            }
+            if SIMPLIFIED.contains(c) {
+                counts.simplified += 1;
+            } else if TRADITIONAL.contains(c) {
+                counts.traditional += 1;
+            } else if JAPANESE.contains(c) {
+                counts.japanese += 1;
+            }
+        }
+        counts
+    }
+
+    fn cjk(&self) -> usize {
+        self.kana + self.hangul + self.han
+    }
+}
+
+/// Rule based detector for Japanese, Chinese and Korean using the proportions of kana, hangul and han.
+/// Works on text too short for statistical detectors, e.g. manga speech bubbles.
+///
+/// - kana make the text Japanese, hangul Korean, han go with whichever of the two is present
+/// - han only text is split between Simplified, Traditional and Japanese by characters unique to each
+/// - text with more letters of other scripts than CJK characters goes to the fallback
+pub struct CjkDetector {
+    fallback: Option<Box<dyn Detector + Send + Sync>>,
+}
+
+impl CjkDetector {
+    /// Create a new CjkDetector
+    /// - `fallback`: detects non CJK text and han only text without telling characters
+    pub fn new(fallback: impl Detector + Send + Sync + 'static) -> Self {
+        Self {
+            fallback: Some(Box::new(fallback)),
+        }
+    }
+
+    /// Create a new CjkDetector that gives up on non CJK text and treats ambiguous han as Chinese
+    pub fn standalone() -> Self {
+        Self { fallback: None }
+    }
+
+    fn fallback_ranked(&self, text: &str) -> Vec<(Language, f64)> {
+        self.fallback
+            .as_ref()
+            .map(|v| v.detect_ranked(text))
+            .unwrap_or_default()
+    }
+}
+
+impl Detector for CjkDetector {
+    fn detect_language(&self, text: &str) -> Option<Language> {
+        self.detect_ranked(text).first().map(|v| v.0)
+    }
+
+    /// Share of the CJK characters pointing to each language
+    fn detect_ranked(&self, text: &str) -> Vec<(Language, f64)> {
+        let counts = ScriptCounts::of(text);
+        let cjk = counts.cjk();
+        if cjk == 0 || counts.other > cjk {
+            return self.fallback_ranked(text);
+        }
+        let share = |n: usize| n as f64 / cjk as f64;
+        if counts.kana > 0 || counts.hangul > 0 {
+            // han belong to the script with more characters
+            let (kana, hangul) = if counts.kana >= counts.hangul {
+                (counts.kana + counts.han, counts.hangul)
+            } else {
+                (counts.kana, counts.hangul + counts.han)
+            };
+            return rank_scores([
+                (Language::Japanese, share(kana)),
+                (Language::Korean, share(hangul)),
+            ]);
+        }
+        let markers = counts.simplified + counts.traditional + counts.japanese;
+        if markers == 0 {
+            let fallback = self.fallback_ranked(text);
+            return match fallback.is_empty() {
+                true => vec![(Language::Chinese, 1.0)],
+                false => fallback,
+            };
+        }
+        let marker_share = |n: usize| n as f64 / markers as f64;
+        rank_scores([
+            (Language::Chinese, marker_share(counts.simplified)),
+            (
+                Language::ChineseTraditional,
+                marker_share(counts.traditional),
+            ),
+            (Language::Japanese, marker_share(counts.japanese)),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Short lines as they appear in speech bubbles, labeled by hand
+    const CORPUS: &[(&str, Language)] = &[
+        ("えっ!?", Language::Japanese),
+        ("ドドド", Language::Japanese),
+        ("ｱﾘｶﾞﾄｳ", Language::Japanese),
+        ("大丈夫か?", Language::Japanese),
+        ("本当に行くの…", Language::Japanese),
+        ("駅前で会おう", Language::Japanese),
+        ("人々", Language::Japanese),
+        ("気楽", Language::Japanese),
+        ("뭐야?!", Language::Korean),
+        ("ㅋㅋㅋ", Language::Korean),
+        ("大韓民國 만세", Language::Korean),
+        ("괜찮아, 나는 學生이야", Language::Korean),
+        ("你说什么?", Language::Chinese),
+        ("这是我们的", Language::Chinese),
+        ("谢谢你!", Language::Chinese),
+        ("为什么", Language::Chinese),
+        ("你說什麼?", Language::ChineseTraditional),
+        ("這是我們的", Language::ChineseTraditional),
+        ("還沒來嗎", Language::ChineseTraditional),
+        ("歡迎光臨", Language::ChineseTraditional),
+    ];
+
+    /// Always detects the same language
+    struct Fixed(Language);
+
+    impl Detector for Fixed {
+        fn detect_language(&self, _: &str) -> Option<Language> {
+            Some(self.0)
+        }
+    }
+
+    #[test]
+    fn corpus() {
+        let d = CjkDetector::standalone();
+        for (text, lang) in CORPUS {
+            assert_eq!(d.detect_language(text), Some(*lang), "{text}");
+        }
+    }
+
+    #[test]
+    fn defers_non_cjk() {
+        let d = CjkDetector::new(Fixed(Language::German));
+        assert_eq!(d.detect_language("Guten Morgen!"), Some(Language::German));
+        assert_eq!(d.detect_language("BOOM 大"), Some(Language::German));
+        assert_eq!(d.detect_language("OK 大丈夫"), Some(Language::German));
+        assert_eq!(d.detect_language("OK, 行くよ"), Some(Language::Japanese));
+        assert_eq!(CjkDetector::standalone().detect_language("!!"), None);
+    }
+
+    #[test]
+    fn mixed_scripts() {
+        let ranked = CjkDetector::standalone().detect_ranked("今日はね학");
+        assert_eq!(ranked[0].0, Language::Japanese);
+        assert!((ranked[0].1 - 0.8).abs() < 1e-9);
+        assert!((ranked[1].1 - 0.2).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    fmt::Write as _,
+    ops::Range,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use aio_translator_interface::{Detector, Language, error::Error};
+
+/// Text lengths in characters the results are split by
+pub const LENGTH_BUCKETS: [Range<usize>; 4] = [0..10, 10..30, 30..100, 100..usize::MAX];
+
+/// Text with the language it is written in
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LabeledSample {
+    pub lang: Language,
+    pub text: String,
+}
+
+/// Reads a corpus with one `language<TAB>text` sample per line, the language as tag like `en` or `zh-Hant`.
+/// Empty lines and lines starting with `#` are skipped.
+pub fn read_labeled_tsv(data: &str) -> anyhow::Result<Vec<LabeledSample>> {
+    let mut samples = vec![];
+    for (i, line) in data.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (tag, text) = line
+            .split_once('\t')
+            .ok_or_else(|| anyhow::anyhow!("line {} has no tab after the language", i + 1))?;
+        let lang =
+            Language::from_tag(tag).ok_or(Error::CouldNotMapLanguage(Some(tag.to_owned())))?;
+        samples.push(LabeledSample {
+            lang,
+            text: text.to_owned(),
+        });
+    }
+    Ok(samples)
+}
+
+pub fn load_labeled_tsv<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<LabeledSample>> {
+    read_labeled_tsv(&std::fs::read_to_string(path)?)
+}
+
+/// Correct guesses and time spent for a group of samples
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Tally {
+    pub total: usize,
+    pub correct: usize,
+    pub elapsed: Duration,
+}
+
+impl Tally {
+    fn add(&mut self, correct: bool, elapsed: Duration) {
+        self.total += 1;
+        self.correct += correct as usize;
+        self.elapsed += elapsed;
+    }
+
+    /// Share of correct guesses from 0 to 1
+    pub fn accuracy(&self) -> f64 {
+        match self.total {
+            0 => 0.0,
+            total => self.correct as f64 / total as f64,
+        }
+    }
+
+    /// Samples detected per second
+    pub fn throughput(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        match secs > 0.0 {
+            true => self.total as f64 / secs,
+            false => f64::INFINITY,
+        }
+    }
+}
+
+/// Results of one detector over a corpus
+#[derive(Clone, Debug)]
+pub struct DetectorReport {
+    pub name: String,
+    pub overall: Tally,
+    /// Per expected language, in order of first appearance in the corpus
+    pub per_language: Vec<(Language, Tally)>,
+    /// Per bucket of [`LENGTH_BUCKETS`]
+    pub per_length: Vec<(Range<usize>, Tally)>,
+    /// How often each expected language was detected as which, `None` if nothing was detected
+    pub confusion: Vec<((Language, Option<Language>), usize)>,
+}
+
+/// Runs `detector` over every sample
+pub fn evaluate_detector<D: Detector + ?Sized>(
+    name: impl Into<String>,
+    detector: &D,
+    samples: &[LabeledSample],
+) -> DetectorReport {
+    let mut report = DetectorReport {
+        name: name.into(),
+        overall: Tally::default(),
+        per_language: vec![],
+        per_length: LENGTH_BUCKETS
+            .iter()
+            .map(|v| (v.clone(), Tally::default()))
+            .collect(),
+        confusion: vec![],
+    };
+    for sample in samples {
+        let start = Instant::now();
+        let detected = detector.detect_language(&sample.text);
+        let elapsed = start.elapsed();
+        let correct = detected == Some(sample.lang);
+
+        report.overall.add(correct, elapsed);
+        entry(&mut report.per_language, sample.lang).add(correct, elapsed);
+        let len = sample.text.chars().count();
+        if let Some((_, tally)) = report.per_length.iter_mut().find(|v| v.0.contains(&len)) {
+            tally.add(correct, elapsed);
+        }
+        *entry(&mut report.confusion, (sample.lang, detected)) += 1;
+    }
+    report
+}
+
+fn entry<K: PartialEq, V: Default>(items: &mut Vec<(K, V)>, key: K) -> &mut V {
+    let i = match items.iter().position(|v| v.0 == key) {
+        Some(i) => i,
+        None => {
+            items.push((key, V::default()));
+            items.len() - 1
+        }
+    };
+    &mut items[i].1
+}
+
+fn name(lang: Option<Language>) -> String {
+    match lang {
+        Some(lang) => lang
+            .to_tag()
+            .map_or_else(|| format!("{lang:?}"), str::to_owned),
+        None => "none".to_owned(),
+    }
+}
+
+fn bucket_name(range: &Range<usize>) -> String {
+    match range.end {
+        usize::MAX => format!("{}+", range.start),
+        end => format!("{}-{}", range.start, end - 1),
+    }
+}
+
+fn tally_row(out: &mut String, label: &str, tally: &Tally) {
+    let _ = writeln!(
+        out,
+        "| {label} | {} | {:.1}% | {:.0} |",
+        tally.total,
+        tally.accuracy() * 100.0,
+        tally.throughput()
+    );
+}
+
+impl DetectorReport {
+    /// Tables of the accuracy per language and length and the confusion matrix
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("## {}\n\n", self.name);
+        let header = "| samples | accuracy | samples/s |\n|---|---:|---:|---:|\n";
+        out.push_str(&format!("| language {header}"));
+        for (lang, tally) in &self.per_language {
+            tally_row(&mut out, &name(Some(*lang)), tally);
+        }
+        tally_row(&mut out, "**all**", &self.overall);
+
+        out.push_str(&format!("\n| length {header}"));
+        for (range, tally) in self.per_length.iter().filter(|v| v.1.total > 0) {
+            tally_row(&mut out, &bucket_name(range), tally);
+        }
+
+        let expected = self.per_language.iter().map(|v| v.0).collect::<Vec<_>>();
+        let mut detected = expected.iter().copied().map(Some).collect::<Vec<_>>();
+        for ((_, lang), _) in &self.confusion {
+            if !detected.contains(lang) {
+                detected.push(*lang);
+            }
+        }
+        out.push_str("\n| expected \\ detected |");
+        for lang in &detected {
+            let _ = write!(out, " {} |", name(*lang));
+        }
+        out.push_str(&format!("\n|---|{}\n", "---:|".repeat(detected.len())));
+        for lang in &expected {
+            let _ = write!(out, "| {} |", name(Some(*lang)));
+            for d in &detected {
+                let count = self
+                    .confusion
+                    .iter()
+                    .find(|v| v.0 == (*lang, *d))
+                    .map_or(0, |v| v.1);
+                let _ = write!(out, " {count} |");
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// One row per detector with its overall accuracy and throughput
+pub fn comparison_markdown(reports: &[DetectorReport]) -> String {
+    let mut out =
+        "| detector | samples | accuracy | samples/s |\n|---|---:|---:|---:|\n".to_owned();
+    for report in reports {
+        tally_row(&mut out, &report.name, &report.overall);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Takes text with a `!` for German, anything else for English
+    struct Bang;
+
+    impl Detector for Bang {
+        fn detect_language(&self, text: &str) -> Option<Language> {
+            match text {
+                "" => None,
+                v if v.contains('!') => Some(Language::German),
+                _ => Some(Language::English),
+            }
+        }
+    }
+
+    const CORPUS: &str = "# lang\ttext
+de\tHallo Welt!
+de\tGuten Morgen
+en\tHello world
+
+en\tThis one is a little longer than ten characters
+en\t";
+
+    #[test]
+    fn read_corpus() {
+        let samples = read_labeled_tsv(CORPUS).unwrap();
+        assert_eq!(samples.len(), 5);
+        assert_eq!(samples[0].lang, Language::German);
+        assert_eq!(samples[4].text, "");
+        assert!(read_labeled_tsv("english text").is_err());
+        assert!(read_labeled_tsv("xx-nope\ttext").is_err());
+    }
+
+    #[test]
+    fn evaluate() {
+        let samples = read_labeled_tsv(CORPUS).unwrap();
+        let report = evaluate_detector("bang", &Bang, &samples);
+        assert_eq!((report.overall.total, report.overall.correct), (5, 3));
+        let (lang, german) = report.per_language[0];
+        assert_eq!(lang, Language::German);
+        assert_eq!(german.accuracy(), 0.5);
+        assert_eq!(report.per_length[0].1.total, 1);
+        assert_eq!(report.per_length[1].1.total, 3);
+        assert_eq!(report.per_length[2].1.total, 1);
+        assert!(
+            report
+                .confusion
+                .contains(&((Language::German, Some(Language::English)), 1))
+        );
+        assert!(report.confusion.contains(&((Language::English, None), 1)));
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("| de | 2 | 50.0% |"));
+        assert!(markdown.contains("| expected \\ detected | de | en | none |"));
+        assert!(markdown.contains("| de | 1 | 1 | 0 |"));
+        assert!(comparison_markdown(&[report]).contains("| bang | 5 | 60.0% |"));
+    }
+}
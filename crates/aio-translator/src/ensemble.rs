@@ -0,0 +1,326 @@
+use aio_translator_interface::{
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, chunk::Limits,
+    error::Error, prompt::PromptBuilder,
+};
+use async_trait::async_trait;
+use futures::future::join_all;
+
+use crate::metrics::chrf;
+
+/// Ranks the candidate translations of one segment
+pub trait Scorer: Send + Sync {
+    /// One score per candidate, the highest wins
+    fn score(&self, source: &str, candidates: &[&str]) -> Vec<f64>;
+}
+
+/// Closures work as scorers, e.g. to rank by the scores of an offline model
+impl<F: Fn(&str, &[&str]) -> Vec<f64> + Send + Sync> Scorer for F {
+    fn score(&self, source: &str, candidates: &[&str]) -> Vec<f64> {
+        self(source, candidates)
+    }
+}
+
+/// Prefers the candidate the others agree with most, by mean chrF against the other candidates
+pub struct Agreement;
+
+impl Scorer for Agreement {
+    fn score(&self, _: &str, candidates: &[&str]) -> Vec<f64> {
+        if candidates.len() < 2 {
+            return vec![0.0; candidates.len()];
+        }
+        candidates
+            .iter()
+            .enumerate()
+            .map(|(i, hyp)| {
+                let total = candidates
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, other)| chrf(hyp, other))
+                    .sum::<f64>();
+                total / (candidates.len() - 1) as f64
+            })
+            .collect()
+    }
+}
+
+/// Prefers candidates whose length is closest to `expected` times the source length.
+/// Catches truncated, empty or runaway outputs.
+pub struct LengthRatio {
+    pub expected: f64,
+}
+
+impl Default for LengthRatio {
+    fn default() -> Self {
+        Self { expected: 1.0 }
+    }
+}
+
+impl Scorer for LengthRatio {
+    fn score(&self, source: &str, candidates: &[&str]) -> Vec<f64> {
+        let source = source.chars().count().max(1) as f64;
+        candidates
+            .iter()
+            .map(|v| {
+                let ratio = v.chars().count().max(1) as f64 / source;
+                -(ratio / self.expected).ln().abs()
+            })
+            .collect()
+    }
+}
+
+/// Translation of one segment by one translator of the ensemble
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    /// Index of the translator
+    pub translator: usize,
+    pub text: String,
+    pub score: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnsembleSegment {
+    /// Index of the chosen candidate
+    pub best: usize,
+    pub candidates: Vec<Candidate>,
+}
+
+impl EnsembleSegment {
+    pub fn best(&self) -> &Candidate {
+        &self.candidates[self.best]
+    }
+}
+
+#[derive(Debug)]
+pub struct EnsembleOutput {
+    pub segments: Vec<EnsembleSegment>,
+    pub lang: Option<Language>,
+    /// Translators that failed, the others were still used
+    pub errors: Vec<(usize, anyhow::Error)>,
+}
+
+/// Runs every segment through all translators concurrently and keeps the best candidate
+pub struct Ensemble<S: Scorer> {
+    translators: Vec<Box<dyn AsyncTranslator + Send + Sync>>,
+    scorer: S,
+}
+
+impl<S: Scorer> Ensemble<S> {
+    /// Create a new Ensemble translator
+    /// - `translators`: asked concurrently for every request
+    /// - `scorer`: picks the best candidate per segment, e.g. [`Agreement`] or [`LengthRatio`]
+    pub fn new(translators: Vec<Box<dyn AsyncTranslator + Send + Sync>>, scorer: S) -> Self {
+        Self {
+            translators,
+            scorer,
+        }
+    }
+
+    /// Translates with every translator and returns all candidates with their scores
+    pub async fn translate_vec_candidates(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<EnsembleOutput> {
+        let results = join_all(
+            self.translators
+                .iter()
+                .map(|t| t.translate_vec(query, context.clone(), from, to)),
+        )
+        .await;
+
+        let mut outputs = vec![];
+        let mut errors = vec![];
+        for (i, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(v) if v.text.len() == query.len() => outputs.push((i, v)),
+                Ok(_) => errors.push((i, Error::NoResponse.into())),
+                Err(e) => errors.push((i, e)),
+            }
+        }
+        if outputs.is_empty() {
+            return Err(match errors.into_iter().next() {
+                Some((_, e)) => e,
+                None => Error::NoResponse.into(),
+            });
+        }
+
+        let segments = query
+            .iter()
+            .enumerate()
+            .map(|(n, source)| {
+                let texts = outputs
+                    .iter()
+                    .map(|(_, v)| v.text[n].as_str())
+                    .collect::<Vec<_>>();
+                let scores = self.scorer.score(source, &texts);
+                let candidates = outputs
+                    .iter()
+                    .zip(texts)
+                    .zip(scores)
+                    .map(|(((translator, _), text), score)| Candidate {
+                        translator: *translator,
+                        text: text.to_owned(),
+                        score,
+                    })
+                    .collect::<Vec<_>>();
+                let best = candidates.iter().enumerate().fold(0, |best, (i, v)| {
+                    match v.score > candidates[best].score {
+                        true => i,
+                        false => best,
+                    }
+                });
+                EnsembleSegment { best, candidates }
+            })
+            .collect();
+        Ok(EnsembleOutput {
+            segments,
+            lang: outputs.iter().find_map(|(_, v)| v.lang),
+            errors,
+        })
+    }
+}
+
+#[async_trait]
+impl<S: Scorer> AsyncTranslator for Ensemble<S> {
+    fn local(&self) -> bool {
+        self.translators.iter().all(|v| v.local())
+    }
+
+    /// Strictest limits of all translators
+    fn limits(&self) -> Limits {
+        self.translators
+            .iter()
+            .map(|v| v.limits())
+            .reduce(Limits::strictest)
+            .unwrap_or_default()
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        self.translators.iter().any(|v| v.supports(from, to))
+    }
+
+    async fn translate(
+        &self,
+        query: &str,
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        let mut trans = self
+            .translate_vec(&[query.to_owned()], context, from, to)
+            .await?;
+        Ok(TranslationOutput {
+            text: trans.text.remove(0),
+            lang: trans.lang,
+        })
+    }
+
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        let out = self
+            .translate_vec_candidates(query, context, from, to)
+            .await?;
+        Ok(TranslationListOutput {
+            text: out
+                .segments
+                .into_iter()
+                .map(|mut v| v.candidates.swap_remove(v.best).text)
+                .collect(),
+            lang: out.lang,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Fake;
+
+    fn translators(texts: Vec<Vec<&'static str>>) -> Vec<Box<dyn AsyncTranslator + Send + Sync>> {
+        texts
+            .into_iter()
+            .map(|v| {
+                let t = match v.is_empty() {
+                    true => Fake::echo().failing(|| Error::NoResponse),
+                    false => Fake::answering_each(v),
+                };
+                Box::new(t) as Box<dyn AsyncTranslator + Send + Sync>
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn pick_by_agreement() {
+        let t = Ensemble::new(
+            translators(vec![
+                vec!["a dog ran", "hello"],
+                vec!["the cat sat", "hello there"],
+                vec![],
+                vec!["the cat sat down", "hello there"],
+            ]),
+            Agreement,
+        );
+        let query = ["x", "y"].map(String::from).to_vec();
+        let out = t
+            .translate_vec_candidates(&query, None, None, &Language::English)
+            .await
+            .unwrap();
+        assert_eq!(out.errors.len(), 1);
+        assert_eq!(out.errors[0].0, 2);
+        assert_eq!(out.segments[0].candidates.len(), 3);
+        assert_ne!(out.segments[0].best().translator, 0);
+        assert_eq!(out.segments[1].best().text, "hello there");
+    }
+
+    #[tokio::test]
+    async fn pick_by_length_ratio() {
+        let t = Ensemble::new(
+            translators(vec![
+                vec![""],
+                vec!["Hallo Welt"],
+                vec!["Hallo Welt Hallo Welt"],
+            ]),
+            LengthRatio::default(),
+        );
+        let out = t
+            .translate("hello world", None, None, &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(out.text, "Hallo Welt");
+
+        let t = Ensemble::new(
+            translators(vec![vec!["short"], vec!["much longer"]]),
+            |_: &str, c: &[&str]| c.iter().map(|v| v.len() as f64).collect(),
+        );
+        let out = t
+            .translate("x", None, None, &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(out.text, "much longer");
+    }
+
+    #[tokio::test]
+    async fn cancel_mid_flight() {
+        let t = Ensemble::new(
+            vec![
+                Box::new(Fake::answering("fast")),
+                Box::new(Fake::answering("slow").with_latency(1000)),
+            ],
+            Agreement,
+        );
+        let r = tokio::time::timeout(
+            std::time::Duration::from_millis(20),
+            t.translate("x", None, None, &Language::German),
+        )
+        .await;
+        assert!(r.is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use std::ops::Range;
+
+use aio_translator_interface::{Detector, Language, detect::rank_scores};
+
+/// A detector of an [`EnsembleDetector`] with its weight
+pub struct Voter {
+    detector: Box<dyn Detector + Send + Sync>,
+    weight: f64,
+    /// Text lengths in characters the detector votes on
+    lengths: Range<usize>,
+}
+
+impl Voter {
+    /// Create a new Voter
+    /// - `weight`: how much its confidences count
+    /// - `lengths`: text lengths in characters it votes on, e.g. `0..30` for short text only
+    pub fn new(
+        detector: impl Detector + Send + Sync + 'static,
+        weight: f64,
+        lengths: Range<usize>,
+    ) -> Self {
+        Self {
+            detector: Box::new(detector),
+            weight,
+            lengths,
+        }
+    }
+
+    /// Create a new Voter that votes on text of any length
+    pub fn any_length(detector: impl Detector + Send + Sync + 'static, weight: f64) -> Self {
+        Self::new(detector, weight, 0..usize::MAX)
+    }
+}
+
+/// Combines several detectors by adding up their weighted confidences
+pub struct EnsembleDetector {
+    voters: Vec<Voter>,
+}
+
+impl EnsembleDetector {
+    /// Create a new EnsembleDetector
+    /// - `voters`: detectors with their weights, ones without confidences vote with 1.0 for their guess
+    pub fn new(voters: Vec<Voter>) -> Self {
+        Self { voters }
+    }
+}
+
+impl Detector for EnsembleDetector {
+    fn detect_language(&self, text: &str) -> Option<Language> {
+        self.detect_ranked(text).first().map(|v| v.0)
+    }
+
+    /// Votes of the detectors responsible for the length of `text`, divided by their total weight
+    fn detect_ranked(&self, text: &str) -> Vec<(Language, f64)> {
+        let len = text.trim().chars().count();
+        let voters = self
+            .voters
+            .iter()
+            .filter(|v| v.lengths.contains(&len) && v.weight > 0.0)
+            .collect::<Vec<_>>();
+        let total = voters.iter().map(|v| v.weight).sum::<f64>();
+        rank_scores(voters.iter().flat_map(|voter| {
+            voter
+                .detector
+                .detect_ranked(text)
+                .into_iter()
+                .map(move |(lang, confidence)| (lang, confidence * voter.weight / total))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Always detects the same ranking
+    struct Fixed(Vec<(Language, f64)>);
+
+    impl Detector for Fixed {
+        fn detect_language(&self, _: &str) -> Option<Language> {
+            self.0.first().map(|v| v.0)
+        }
+
+        fn detect_ranked(&self, _: &str) -> Vec<(Language, f64)> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn weighted_vote() {
+        let d = EnsembleDetector::new(vec![
+            Voter::any_length(Fixed(vec![(Language::German, 0.9)]), 1.0),
+            Voter::any_length(
+                Fixed(vec![(Language::Dutch, 0.6), (Language::German, 0.4)]),
+                2.0,
+            ),
+        ]);
+        // german: 0.9 / 3 + 0.4 * 2 / 3, dutch: 0.6 * 2 / 3
+        let ranked = d.detect_ranked("Guten Tag");
+        assert_eq!(ranked[0].0, Language::German);
+        assert!((ranked[0].1 - 1.7 / 3.0).abs() < 1e-9);
+        assert!((ranked[1].1 - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn by_text_length() {
+        let d = EnsembleDetector::new(vec![
+            Voter::new(Fixed(vec![(Language::Japanese, 1.0)]), 1.0, 0..5),
+            Voter::new(Fixed(vec![(Language::Chinese, 1.0)]), 1.0, 5..usize::MAX),
+        ]);
+        assert_eq!(d.detect_language("こんにちは"), Some(Language::Chinese));
+        assert_eq!(d.detect_language(" はい "), Some(Language::Japanese));
+        assert_eq!(EnsembleDetector::new(vec![]).detect_language("hi"), None);
+    }
+}
@@ -0,0 +1,527 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    path::Path,
+};
+
+use aio_translator_interface::{
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput,
+    chunk::Limits,
+    detect::{is_han, is_hangul, is_kana},
+    error::Error,
+    prompt::PromptBuilder,
+};
+use async_trait::async_trait;
+use fancy_regex::{Regex, escape};
+
+use crate::mask::{next_free, sentinel, sentinel_spans, unmask};
+
+/// A source term and its required translation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GlossaryTerm {
+    pub source: String,
+    pub target: String,
+    /// Only match the source term with the same casing
+    pub case_sensitive: bool,
+    /// Only match the source term if it isnt part of a larger word.
+    /// CJK characters never count as part of a word.
+    pub whole_word: bool,
+}
+
+impl GlossaryTerm {
+    pub fn new(source: impl Into<String>, target: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            target: target.into(),
+            case_sensitive: false,
+            whole_word: true,
+        }
+    }
+}
+
+/// Terms grouped by language pair
+#[derive(Clone, Default)]
+pub struct Glossary {
+    terms: HashMap<(Language, Language), Vec<GlossaryTerm>>,
+    /// Compiled source pattern per source term and case sensitivity
+    patterns: HashMap<(String, bool), Regex>,
+}
+
+impl Glossary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, from: Language, to: Language, term: GlossaryTerm) {
+        if let Entry::Vacant(entry) = self
+            .patterns
+            .entry((term.source.clone(), term.case_sensitive))
+        {
+            let flags = if term.case_sensitive { "" } else { "(?i)" };
+            if let Ok(re) = Regex::new(&format!("{flags}{}", escape(&term.source))) {
+                entry.insert(re);
+            }
+        }
+        let terms = self.terms.entry((from, to)).or_default();
+        terms.push(term);
+        // longer terms first so "Dark Lord" wins over "Lord"
+        terms.sort_by_key(|v| std::cmp::Reverse(v.source.chars().count()));
+    }
+
+    pub fn terms(&self, from: Language, to: Language) -> &[GlossaryTerm] {
+        self.terms
+            .get(&(from, to))
+            .map(|v| v.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Reads a glossary with the columns `from`, `to`, `source`, `target` and the optional
+    /// columns `case_sensitive` and `whole_word`. Languages are language tags like `en` or `ja`.
+    pub fn from_csv(data: &str) -> anyhow::Result<Self> {
+        Self::from_delimited(data, b',')
+    }
+
+    /// Same as [`Glossary::from_csv`], but tab separated
+    pub fn from_tsv(data: &str) -> anyhow::Result<Self> {
+        Self::from_delimited(data, b'\t')
+    }
+
+    pub fn load_csv<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        Self::from_csv(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn load_tsv<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        Self::from_tsv(&std::fs::read_to_string(path)?)
+    }
+
+    fn from_delimited(data: &str, delimiter: u8) -> anyhow::Result<Self> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .trim(csv::Trim::All)
+            .from_reader(data.as_bytes());
+        let headers = rdr.headers()?.clone();
+        let column = |name: &str| headers.iter().position(|v| v.eq_ignore_ascii_case(name));
+        let required = |name: &str| {
+            column(name).ok_or_else(|| anyhow::anyhow!("glossary is missing the `{name}` column"))
+        };
+        let (from, to, source, target) = (
+            required("from")?,
+            required("to")?,
+            required("source")?,
+            required("target")?,
+        );
+        let (case_sensitive, whole_word) = (column("case_sensitive"), column("whole_word"));
+
+        let mut glossary = Self::new();
+        for record in rdr.records() {
+            let record = record?;
+            let lang = |i: usize| {
+                let tag = record.get(i).unwrap_or_default();
+                Language::from_tag(tag).ok_or(Error::CouldNotMapLanguage(Some(tag.to_owned())))
+            };
+            let flag = |i: Option<usize>, default: bool| match i
+                .and_then(|i| record.get(i))
+                .map(|v| v.to_lowercase())
+            {
+                Some(v) if matches!(v.as_str(), "true" | "yes" | "y" | "1") => true,
+                Some(v) if matches!(v.as_str(), "false" | "no" | "n" | "0") => false,
+                _ => default,
+            };
+            let mut term = GlossaryTerm::new(
+                record.get(source).unwrap_or_default(),
+                record.get(target).unwrap_or_default(),
+            );
+            if term.source.is_empty() {
+                continue;
+            }
+            term.case_sensitive = flag(case_sensitive, term.case_sensitive);
+            term.whole_word = flag(whole_word, term.whole_word);
+            glossary.insert(lang(from)?, lang(to)?, term);
+        }
+        Ok(glossary)
+    }
+
+    /// Terms that apply to a translation into `to`. Without a source language every pair into `to` is used.
+    fn applicable(&self, from: Option<Language>, to: Language) -> Vec<(&GlossaryTerm, &Regex)> {
+        let mut terms = self
+            .terms
+            .iter()
+            .filter(|((f, t), _)| *t == to && from.is_none_or(|from| from == *f))
+            .flat_map(|(_, terms)| terms)
+            .filter_map(|term| {
+                let re = self
+                    .patterns
+                    .get(&(term.source.clone(), term.case_sensitive))?;
+                Some((term, re))
+            })
+            .collect::<Vec<_>>();
+        terms.sort_by_key(|v| std::cmp::Reverse(v.0.source.chars().count()));
+        terms
+    }
+}
+
+/// A glossary term whose sentinel did not survive the translation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LostTerm {
+    /// Index of the segment in the query
+    pub segment: usize,
+    pub source: String,
+    pub target: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GlossaryReport {
+    pub lost: Vec<LostTerm>,
+}
+
+/// CJK characters never continue a word, terms match inside unspaced text
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() && !(is_kana(ch) || is_hangul(ch) || is_han(ch))
+}
+
+/// Masked text, index of the first sentinel and the term of each sentinel
+type Masked<'a> = (String, usize, Vec<&'a GlossaryTerm>);
+
+/// Replaces every term occurrence with a sentinel.
+/// All terms are matched against the original text, overlapping matches go to the longest
+/// and sentinels of other wrappers are never matched into.
+fn mask<'a>(text: &str, terms: &[(&'a GlossaryTerm, &Regex)]) -> Masked<'a> {
+    let offset = next_free(text);
+    let mut matches = vec![];
+    for (term, re) in terms {
+        for m in re.find_iter(text).flatten() {
+            let before = text[..m.start()].chars().next_back();
+            let after = text[m.end()..].chars().next();
+            if term.whole_word
+                && (before.is_some_and(is_word_char) || after.is_some_and(is_word_char))
+            {
+                continue;
+            }
+            matches.push((m.range(), *term));
+        }
+    }
+    matches.sort_by_key(|(range, _)| (std::cmp::Reverse(range.len()), range.start));
+    let mut taken = sentinel_spans(text);
+    let mut kept = vec![];
+    for (range, term) in matches {
+        if taken
+            .iter()
+            .any(|v| v.start < range.end && range.start < v.end)
+        {
+            continue;
+        }
+        taken.push(range.clone());
+        kept.push((range, term));
+    }
+    kept.sort_by_key(|(range, _)| range.start);
+
+    let mut out = String::with_capacity(text.len());
+    let mut used = vec![];
+    let mut last = 0;
+    for (range, term) in kept {
+        out.push_str(&text[last..range.start]);
+        out.push_str(&sentinel(offset + used.len()));
+        used.push(term);
+        last = range.end;
+    }
+    out.push_str(&text[last..]);
+    (out, offset, used)
+}
+
+/// Enforces consistent terminology by masking glossary terms before the translation
+/// and inserting the mapped target terms afterwards
+pub struct GlossaryEnforcer<T: AsyncTranslator> {
+    t: T,
+    glossary: Glossary,
+    /// Fail with [`Error::TermsLost`] instead of returning a translation with missing terms
+    strict: bool,
+}
+
+impl<T: AsyncTranslator> GlossaryEnforcer<T> {
+    /// Create a new GlossaryEnforcer wrapper
+    /// - `strict`: return [`Error::TermsLost`] if a term went missing during the translation
+    pub fn new(t: T, glossary: Glossary, strict: bool) -> Self {
+        Self {
+            t,
+            glossary,
+            strict,
+        }
+    }
+
+    pub fn glossary(&self) -> &Glossary {
+        &self.glossary
+    }
+
+    pub fn glossary_mut(&mut self) -> &mut Glossary {
+        &mut self.glossary
+    }
+
+    /// Translates and reports all terms that were lost instead of failing
+    pub async fn translate_vec_with_report(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<(TranslationListOutput, GlossaryReport)> {
+        let terms = self.glossary.applicable(from, *to);
+        if terms.is_empty() {
+            let trans = self.t.translate_vec(query, context, from, to).await?;
+            return Ok((trans, GlossaryReport::default()));
+        }
+        let (masked, used): (Vec<_>, Vec<_>) = query
+            .iter()
+            .map(|q| {
+                let (text, offset, used) = mask(q, &terms);
+                (text, (offset, used))
+            })
+            .unzip();
+        let mut trans = self.t.translate_vec(&masked, context, from, to).await?;
+        if trans.text.len() != query.len() {
+            return Err(Error::NoResponse.into());
+        }
+        let mut report = GlossaryReport::default();
+        for (segment, (text, (offset, used))) in trans.text.iter_mut().zip(&used).enumerate() {
+            *text = restore(text, *offset, used, segment, &mut report);
+        }
+        Ok((trans, report))
+    }
+
+    fn check(&self, report: &GlossaryReport) -> Result<(), Error> {
+        if self.strict && !report.lost.is_empty() {
+            return Err(Error::TermsLost(
+                report.lost.iter().map(|v| v.source.clone()).collect(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn restore(
+    text: &str,
+    offset: usize,
+    used: &[&GlossaryTerm],
+    segment: usize,
+    report: &mut GlossaryReport,
+) -> String {
+    let targets = used.iter().map(|v| v.target.clone()).collect::<Vec<_>>();
+    let (text, counts) = unmask(text, offset, &targets);
+    for (term, count) in used.iter().zip(counts) {
+        if count == 0 {
+            report.lost.push(LostTerm {
+                segment,
+                source: term.source.clone(),
+                target: term.target.clone(),
+            });
+        }
+    }
+    text
+}
+
+#[async_trait]
+impl<T: AsyncTranslator + Send + Sync> AsyncTranslator for GlossaryEnforcer<T> {
+    fn local(&self) -> bool {
+        self.t.local()
+    }
+
+    fn limits(&self) -> Limits {
+        self.t.limits()
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        self.t.supports(from, to)
+    }
+
+    async fn translate(
+        &self,
+        query: &str,
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        let terms = self.glossary.applicable(from, *to);
+        let (masked, offset, used) = mask(query, &terms);
+        let mut trans = self.t.translate(&masked, context, from, to).await?;
+        let mut report = GlossaryReport::default();
+        trans.text = restore(&trans.text, offset, &used, 0, &mut report);
+        self.check(&report)?;
+        Ok(trans)
+    }
+
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        let (trans, report) = self
+            .translate_vec_with_report(query, context, from, to)
+            .await?;
+        self.check(&report)?;
+        Ok(trans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Fake;
+
+    struct DropSecond;
+
+    #[async_trait]
+    impl AsyncTranslator for DropSecond {
+        fn local(&self) -> bool {
+            true
+        }
+
+        async fn translate(
+            &self,
+            query: &str,
+            _: Option<PromptBuilder>,
+            from: Option<Language>,
+            _: &Language,
+        ) -> anyhow::Result<TranslationOutput> {
+            Ok(TranslationOutput {
+                text: query.replace(&sentinel(1), ""),
+                lang: from,
+            })
+        }
+
+        async fn translate_vec(
+            &self,
+            query: &[String],
+            _: Option<PromptBuilder>,
+            from: Option<Language>,
+            _: &Language,
+        ) -> anyhow::Result<TranslationListOutput> {
+            Ok(TranslationListOutput {
+                text: query.iter().map(|q| q.replace(&sentinel(1), "")).collect(),
+                lang: from,
+            })
+        }
+    }
+
+    const CSV: &str = "from,to,source,target,case_sensitive,whole_word
+en,de,Dark Lord,Dunkler Lord,false,true
+en,de,Lord,Herr,false,true
+en,de,Kai,Kai,true,true
+";
+
+    #[test]
+    fn load_csv() {
+        let glossary = Glossary::from_csv(CSV).unwrap();
+        let terms = glossary.terms(Language::English, Language::German);
+        assert_eq!(terms.len(), 3);
+        assert_eq!(terms[0].source, "Dark Lord");
+        assert!(
+            terms
+                .iter()
+                .find(|v| v.source == "Kai")
+                .unwrap()
+                .case_sensitive
+        );
+
+        let tsv = CSV.replace(',', "\t");
+        assert_eq!(
+            Glossary::from_tsv(&tsv)
+                .unwrap()
+                .terms(Language::English, Language::German)
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn mask_rules() {
+        let glossary = Glossary::from_csv(CSV).unwrap();
+        let terms = glossary.applicable(Some(Language::English), Language::German);
+        let (masked, _, used) = mask("The dark lord and his Lordship met kai and Kai", &terms);
+        assert_eq!(
+            masked,
+            format!(
+                "The {} and his Lordship met kai and {}",
+                sentinel(0),
+                sentinel(1)
+            )
+        );
+        assert_eq!(used[0].target, "Dunkler Lord");
+        assert_eq!(used[1].target, "Kai");
+
+        // half-width katakana and hangul jamo don't continue a word
+        let (masked, _, _) = mask("ｶKai Kaiᄀ", &terms);
+        assert_eq!(masked, format!("ｶ{} {}ᄀ", sentinel(0), sentinel(1)));
+    }
+
+    #[test]
+    fn mask_keeps_sentinels_intact() {
+        let mut glossary = Glossary::new();
+        for (source, target) in [("Lord", "Herr"), ("0", "null"), ("1", "eins")] {
+            glossary.insert(
+                Language::English,
+                Language::German,
+                GlossaryTerm::new(source, target),
+            );
+        }
+        let terms = glossary.applicable(Some(Language::English), Language::German);
+        // "0" must not match inside the sentinel inserted for "Lord"
+        let (masked, offset, used) = mask("Lord 0", &terms);
+        assert_eq!(masked, format!("{} {}", sentinel(0), sentinel(1)));
+        assert_eq!(offset, 0);
+        assert_eq!(
+            used.iter().map(|v| v.target.as_str()).collect::<Vec<_>>(),
+            ["Herr", "null"]
+        );
+        let targets = used.iter().map(|v| v.target.clone()).collect::<Vec<_>>();
+        assert_eq!(unmask(&masked, offset, &targets).0, "Herr null");
+
+        // nor inside sentinels another wrapper put there
+        let (masked, offset, used) = mask(&format!("{} Lord 1", sentinel(0)), &terms);
+        assert_eq!(
+            masked,
+            format!("{} {} {}", sentinel(0), sentinel(1), sentinel(2))
+        );
+        assert_eq!((offset, used.len()), (1, 2));
+    }
+
+    #[tokio::test]
+    async fn report_lost_terms() {
+        let glossary = Glossary::from_csv(CSV).unwrap();
+        let t = GlossaryEnforcer::new(DropSecond, glossary, true);
+        let (out, report) = t
+            .translate_vec_with_report(
+                &["Lord Kai".to_owned()],
+                None,
+                Some(Language::English),
+                &Language::German,
+            )
+            .await
+            .unwrap();
+        assert_eq!(out.text, vec!["Herr ".to_owned()]);
+        assert_eq!(report.lost.len(), 1);
+        assert_eq!(report.lost[0].source, "Kai");
+
+        assert!(
+            t.translate("Lord Kai", None, Some(Language::English), &Language::German)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn segment_count_mismatch() {
+        let glossary = Glossary::from_csv(CSV).unwrap();
+        let query = ["Lord Kai".to_owned(), "Kai".to_owned()];
+        for answer in [vec!["Herr"], vec!["Herr", "Kai", "⟦9⟧"]] {
+            let t = GlossaryEnforcer::new(Fake::answering_batch(answer), glossary.clone(), false);
+            let err = t
+                .translate_vec_with_report(&query, None, Some(Language::English), &Language::German)
+                .await
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast_ref::<Error>(),
+                Some(Error::NoResponse)
+            ));
+        }
+    }
+}
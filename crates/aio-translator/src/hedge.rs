@@ -0,0 +1,260 @@
+use std::{
+    pin::pin,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use aio_translator_interface::{
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, chunk::Limits,
+    prompt::PromptBuilder,
+};
+use async_trait::async_trait;
+
+/// How often a [`Hedged`] translator needed the secondary
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HedgeStats {
+    pub requests: u64,
+    /// The primary was too slow and the secondary was asked as well
+    pub hedged: u64,
+    /// The primary failed before the threshold and the secondary was asked instead
+    pub fallbacks: u64,
+    /// The answer of the secondary was used
+    pub secondary_wins: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    requests: AtomicU64,
+    hedged: AtomicU64,
+    fallbacks: AtomicU64,
+    secondary_wins: AtomicU64,
+}
+
+/// Asks the secondary as well if the primary takes longer than `delay`.
+/// The first successful answer is used and the other request is dropped.
+pub struct Hedged<P: AsyncTranslator, S: AsyncTranslator> {
+    primary: P,
+    secondary: S,
+    delay: Duration,
+    counters: Counters,
+}
+
+impl<P: AsyncTranslator, S: AsyncTranslator> Hedged<P, S> {
+    /// Create a new Hedged wrapper
+    /// - `primary`: asked first
+    /// - `secondary`: asked once `delay` passed without an answer from the primary, or if it failed
+    /// - `delay`: latency threshold, e.g. the 95th percentile of the primary
+    pub fn new(primary: P, secondary: S, delay: Duration) -> Self {
+        Self {
+            primary,
+            secondary,
+            delay,
+            counters: Counters::default(),
+        }
+    }
+
+    pub fn stats(&self) -> HedgeStats {
+        let c = &self.counters;
+        HedgeStats {
+            requests: c.requests.load(Ordering::Relaxed),
+            hedged: c.hedged.load(Ordering::Relaxed),
+            fallbacks: c.fallbacks.load(Ordering::Relaxed),
+            secondary_wins: c.secondary_wins.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Futures are lazy, so the secondary request is only sent once it is polled
+    async fn race<R>(
+        &self,
+        primary: impl Future<Output = anyhow::Result<R>>,
+        secondary: impl Future<Output = anyhow::Result<R>>,
+    ) -> anyhow::Result<R> {
+        let c = &self.counters;
+        c.requests.fetch_add(1, Ordering::Relaxed);
+        let mut primary = pin!(primary);
+        tokio::select! {
+            r = &mut primary => {
+                if r.is_ok() {
+                    return r;
+                }
+                c.fallbacks.fetch_add(1, Ordering::Relaxed);
+                let s = secondary.await;
+                if s.is_ok() {
+                    c.secondary_wins.fetch_add(1, Ordering::Relaxed);
+                    return s;
+                }
+                return r;
+            }
+            _ = tokio::time::sleep(self.delay) => {}
+        }
+
+        c.hedged.fetch_add(1, Ordering::Relaxed);
+        let mut secondary = pin!(secondary);
+        tokio::select! {
+            r = &mut primary => match r {
+                Ok(_) => r,
+                Err(_) => {
+                    let s = secondary.await;
+                    if s.is_ok() {
+                        c.secondary_wins.fetch_add(1, Ordering::Relaxed);
+                        return s;
+                    }
+                    r
+                }
+            },
+            s = &mut secondary => match s {
+                Ok(_) => {
+                    c.secondary_wins.fetch_add(1, Ordering::Relaxed);
+                    s
+                }
+                Err(_) => primary.await,
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl<P, S> AsyncTranslator for Hedged<P, S>
+where
+    P: AsyncTranslator + Send + Sync,
+    S: AsyncTranslator + Send + Sync,
+{
+    fn local(&self) -> bool {
+        self.primary.local() && self.secondary.local()
+    }
+
+    /// Both get the same requests, so the limits of both apply
+    fn limits(&self) -> Limits {
+        self.primary.limits().strictest(self.secondary.limits())
+    }
+
+    /// The secondary answers alone if the primary rejects the pair
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        self.primary.supports(from, to) || self.secondary.supports(from, to)
+    }
+
+    async fn translate(
+        &self,
+        query: &str,
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        self.race(
+            self.primary.translate(query, context.clone(), from, to),
+            self.secondary.translate(query, context, from, to),
+        )
+        .await
+    }
+
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        self.race(
+            self.primary.translate_vec(query, context.clone(), from, to),
+            self.secondary.translate_vec(query, context, from, to),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aio_translator_interface::error::Error;
+
+    use super::*;
+    use crate::test_support::Fake;
+
+    fn slow(name: &'static str, millis: u64) -> Fake {
+        Fake::answering(name).with_latency(millis)
+    }
+
+    async fn winner<P, S>(t: &Hedged<P, S>) -> String
+    where
+        P: AsyncTranslator + Send + Sync,
+        S: AsyncTranslator + Send + Sync,
+    {
+        let r = t.translate("", None, None, &Language::German).await;
+        r.unwrap().text
+    }
+
+    #[tokio::test]
+    async fn hedge_slow_primary() {
+        let t = Hedged::new(
+            slow("primary", 200),
+            slow("secondary", 0),
+            Duration::from_millis(20),
+        );
+        assert_eq!(winner(&t).await, "secondary");
+        assert_eq!((t.stats().hedged, t.stats().secondary_wins), (1, 1));
+
+        let t = Hedged::new(
+            slow("primary", 0),
+            slow("secondary", 0),
+            Duration::from_millis(20),
+        );
+        assert_eq!(winner(&t).await, "primary");
+        assert_eq!(t.stats().hedged, 0);
+    }
+
+    #[tokio::test]
+    async fn failures_fall_through() {
+        let t = Hedged::new(
+            slow("primary", 0).failing(|| Error::NoResponse),
+            slow("secondary", 0),
+            Duration::from_millis(20),
+        );
+        assert_eq!(winner(&t).await, "secondary");
+        assert_eq!((t.stats().hedged, t.stats().fallbacks), (0, 1));
+
+        // the hedge failed, so the slow primary still answers
+        let t = Hedged::new(
+            slow("primary", 50),
+            slow("secondary", 0).failing(|| Error::NoResponse),
+            Duration::from_millis(10),
+        );
+        assert_eq!(winner(&t).await, "primary");
+        assert_eq!(
+            t.stats(),
+            HedgeStats {
+                requests: 1,
+                hedged: 1,
+                fallbacks: 0,
+                secondary_wins: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn limits_and_batches() {
+        let t = Hedged::new(
+            slow("primary", 200).with_limits(Limits {
+                max_segments: Some(50),
+                ..Default::default()
+            }),
+            slow("secondary", 0).with_limits(Limits {
+                max_segments: Some(10),
+                max_request_len: Some(1000),
+                ..Default::default()
+            }),
+            Duration::from_millis(20),
+        );
+        let limits = t.limits();
+        assert_eq!(
+            (limits.max_segments, limits.max_request_len),
+            (Some(10), Some(1000))
+        );
+
+        let query = vec!["a".to_owned(), "b".to_owned()];
+        let out = t
+            .translate_vec(&query, None, None, &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(out.text, vec!["secondary", "secondary"]);
+        assert_eq!(t.stats().secondary_wins, 1);
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use aio_translator_interface::{
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, chunk::Limits,
+    error::Error, prompt::PromptBuilder,
+};
+use async_trait::async_trait;
+
+/// Health of one key in a [`KeyPool`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyStatus {
+    pub weight: u32,
+    /// Requests sent with the key
+    pub requests: u64,
+    /// Requests rejected because of quota or credentials
+    pub failures: u64,
+    /// Time until the key is used again
+    pub benched_for: Option<Duration>,
+}
+
+#[derive(Default)]
+struct KeyState {
+    /// Smooth weighted round robin counter
+    current: i64,
+    requests: u64,
+    failures: u64,
+    benched_until: Option<Instant>,
+}
+
+/// Spreads requests over translators using different api keys.
+/// Keys that run out of quota, are rate limited or get rejected are benched for a while.
+pub struct KeyPool<T: AsyncTranslator> {
+    keys: Vec<(T, u32)>,
+    state: Mutex<Vec<KeyState>>,
+    /// How long a failing key is left out if the api didn't say
+    bench: Duration,
+}
+
+impl<T: AsyncTranslator> KeyPool<T> {
+    /// Create a new KeyPool wrapper that rotates through the keys
+    /// - `keys`: the same translator constructed with different keys
+    /// - `bench`: how long a key is left out after quota or auth errors
+    pub fn new(keys: Vec<T>, bench: Duration) -> Self {
+        Self::weighted(keys.into_iter().map(|v| (v, 1)).collect(), bench)
+    }
+
+    /// Create a new KeyPool wrapper where every key gets a share of the requests proportional to its weight
+    /// - `keys`: translators with their weight, e.g. the quota of the key
+    /// - `bench`: how long a key is left out after quota or auth errors
+    pub fn weighted(keys: Vec<(T, u32)>, bench: Duration) -> Self {
+        assert!(!keys.is_empty(), "KeyPool needs at least one key");
+        let state = keys.iter().map(|_| KeyState::default()).collect();
+        Self {
+            keys: keys.into_iter().map(|(t, w)| (t, w.max(1))).collect(),
+            state: Mutex::new(state),
+            bench,
+        }
+    }
+
+    pub fn status(&self) -> Vec<KeyStatus> {
+        let now = Instant::now();
+        let state = self.state.lock().unwrap();
+        state
+            .iter()
+            .zip(&self.keys)
+            .map(|(key, (_, weight))| KeyStatus {
+                weight: *weight,
+                requests: key.requests,
+                failures: key.failures,
+                benched_for: key.benched_until.filter(|v| *v > now).map(|v| v - now),
+            })
+            .collect()
+    }
+
+    /// Next key that is neither benched nor already tried for this request.
+    /// Returns how long until a benched key is back if there is none.
+    fn pick(&self, tried: &[bool]) -> Result<usize, Option<Duration>> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let healthy = (0..self.keys.len())
+            .filter(|i| !tried[*i] && state[*i].benched_until.is_none_or(|v| v <= now))
+            .collect::<Vec<_>>();
+        if healthy.is_empty() {
+            let wait = state
+                .iter()
+                .filter_map(|v| v.benched_until)
+                .min()
+                .map(|v| v.saturating_duration_since(now));
+            return Err(wait);
+        }
+
+        let total = healthy.iter().map(|i| self.keys[*i].1 as i64).sum::<i64>();
+        for i in &healthy {
+            state[*i].current += self.keys[*i].1 as i64;
+        }
+        let best = *healthy
+            .iter()
+            .max_by_key(|i| (state[**i].current, std::cmp::Reverse(**i)))
+            .unwrap();
+        state[best].current -= total;
+        state[best].requests += 1;
+        Ok(best)
+    }
+
+    fn bench_key(&self, index: usize, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        let key = &mut state[index];
+        key.failures += 1;
+        key.current = 0;
+        key.benched_until = Some(Instant::now() + duration);
+    }
+
+    /// Runs `f` with the next healthy key, moving on to the next one while keys get benched
+    async fn with_key<'a, R, F>(&'a self, f: impl Fn(&'a T) -> F) -> anyhow::Result<R>
+    where
+        F: Future<Output = anyhow::Result<R>>,
+    {
+        let mut tried = vec![false; self.keys.len()];
+        let mut last = None;
+        loop {
+            let index = match self.pick(&tried) {
+                Ok(v) => v,
+                // every key is benched, lets a RateLimiter retry once one is back
+                Err(wait) => return last.unwrap_or(Err(Error::RateLimited(wait).into())),
+            };
+            tried[index] = true;
+            let r = f(&self.keys[index].0).await;
+            let bench = match r.as_ref().err().and_then(|e| e.downcast_ref::<Error>()) {
+                Some(Error::Unauthorized | Error::QuotaExceeded) => self.bench,
+                Some(Error::RateLimited(retry_after)) => retry_after.unwrap_or(self.bench),
+                _ => return r,
+            };
+            self.bench_key(index, bench);
+            last = Some(r);
+        }
+    }
+}
+
+#[async_trait]
+impl<T: AsyncTranslator + Send + Sync> AsyncTranslator for KeyPool<T> {
+    fn local(&self) -> bool {
+        self.keys[0].0.local()
+    }
+
+    fn limits(&self) -> Limits {
+        self.keys[0].0.limits()
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        self.keys[0].0.supports(from, to)
+    }
+
+    async fn translate(
+        &self,
+        query: &str,
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        self.with_key(|t| t.translate(query, context.clone(), from, to))
+            .await
+    }
+
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        self.with_key(|t| t.translate_vec(query, context.clone(), from, to))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Fake;
+
+    async fn used<T: AsyncTranslator + Send + Sync>(pool: &KeyPool<T>, n: usize) -> String {
+        let mut out = String::new();
+        for _ in 0..n {
+            let r = pool.translate("", None, None, &Language::German).await;
+            out.push_str(&r.unwrap().text);
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn round_robin_and_weights() {
+        let pool = KeyPool::new(
+            vec![
+                Fake::answering("a"),
+                Fake::answering("b"),
+                Fake::answering("c"),
+            ],
+            Duration::from_secs(60),
+        );
+        assert_eq!(used(&pool, 6).await, "abcabc");
+
+        let pool = KeyPool::weighted(
+            vec![(Fake::answering("a"), 3), (Fake::answering("b"), 1)],
+            Duration::from_secs(60),
+        );
+        assert_eq!(used(&pool, 8).await, "aabaaaba");
+        assert_eq!(pool.status()[1].requests, 2);
+    }
+
+    #[tokio::test]
+    async fn bench_failing_keys() {
+        let keys = vec![
+            Fake::answering("a").failing(|| Error::QuotaExceeded),
+            Fake::answering("b"),
+            Fake::answering("c").failing(|| Error::Unauthorized),
+        ];
+        let pool = KeyPool::new(keys, Duration::from_secs(60));
+        assert_eq!(used(&pool, 3).await, "bbb");
+        let status = pool.status();
+        assert_eq!((status[0].failures, status[1].failures), (1, 0));
+        assert!(status[2].benched_for.is_some());
+        assert_eq!(status[1].requests, 3);
+
+        let pool = KeyPool::new(
+            vec![Fake::answering("a").failing(|| Error::RateLimited(Some(Duration::from_secs(5))))],
+            Duration::from_secs(60),
+        );
+        let first = pool.translate("", None, None, &Language::German).await;
+        assert!(first.is_err());
+        let Err(second) = pool.translate("", None, None, &Language::German).await else {
+            panic!("key should be benched");
+        };
+        let Some(Error::RateLimited(Some(wait))) = second.downcast_ref::<Error>() else {
+            panic!("{second:?}");
+        };
+        assert!(*wait <= Duration::from_secs(5));
+    }
+}
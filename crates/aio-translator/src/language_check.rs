@@ -0,0 +1,351 @@
+use aio_translator_interface::{
+    AsyncTranslator, Detector, Language, TranslationListOutput, TranslationOutput, chunk::Limits,
+    error::Error, prompt::PromptBuilder,
+};
+use async_trait::async_trait;
+
+use crate::is_valuable_text;
+
+/// How a segment that came out in the wrong language is translated again.
+/// Retried segments are sent on their own, batches tend to drag neighbours into the wrong language.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Retry {
+    /// Sets `from` to the language the detector finds in the source segment
+    DetectSource,
+    /// Clears `from` so the translator detects the source itself
+    AutoSource,
+    /// Sends the segment without the prompt context
+    WithoutContext,
+}
+
+/// A segment that was still in the wrong language after all retries
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WrongLanguage {
+    pub segment: usize,
+    /// Language of the last translation
+    pub detected: Language,
+    pub retries: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LanguageReport {
+    pub wrong: Vec<WrongLanguage>,
+    /// Segments a retry put into the right language
+    pub fixed: Vec<usize>,
+}
+
+/// Chinese detectors rarely tell the scripts apart, so both count as the same language
+fn same_language(a: Language, b: Language) -> bool {
+    let chinese = |v: Language| matches!(v, Language::Chinese | Language::ChineseTraditional);
+    a == b || (chinese(a) && chinese(b))
+}
+
+/// Runs a [`Detector`] over translations and retries segments that aren't in the target language,
+/// e.g. offline models answering in the source language for low resource targets
+pub struct LanguageCheck<T: AsyncTranslator, D: Detector> {
+    t: T,
+    detector: D,
+    retries: Vec<Retry>,
+    min_confidence: f64,
+    strict: bool,
+}
+
+impl<T: AsyncTranslator, D: Detector> LanguageCheck<T, D> {
+    /// Create a new LanguageCheck wrapper
+    /// - `retries`: tried in order until the segment is in the target language, empty only flags it
+    /// - `min_confidence`: detections below it are ignored, 0 trusts every guess
+    /// - `strict`: return [`Error::WrongLanguage`] if a segment stays in the wrong language
+    pub fn new(t: T, detector: D, retries: Vec<Retry>, min_confidence: f64, strict: bool) -> Self {
+        Self {
+            t,
+            detector,
+            retries,
+            min_confidence,
+            strict,
+        }
+    }
+
+    /// Detected language of `text` if it isn't `to`.
+    /// Text without letters like numbers or punctuation and text the detector isn't sure about is never wrong.
+    fn wrong_language(&self, text: &str, to: Language) -> Option<Language> {
+        if !is_valuable_text(text) {
+            return None;
+        }
+        self.detector
+            .detect_confident(text, self.min_confidence)
+            .filter(|v| !same_language(*v, to))
+    }
+
+    /// Retries `query` until it comes out in `to`, returns the translation and the retries needed.
+    /// `Err` holds the language of the last attempt.
+    async fn retry(
+        &self,
+        query: &str,
+        context: &Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+        mut detected: Language,
+    ) -> (Result<String, Language>, usize) {
+        for (i, retry) in self.retries.iter().enumerate() {
+            let (context, from) = match retry {
+                Retry::DetectSource => (
+                    context.clone(),
+                    self.detector
+                        .detect_confident(query, self.min_confidence)
+                        .or(from),
+                ),
+                Retry::AutoSource => (context.clone(), None),
+                Retry::WithoutContext => (None, from),
+            };
+            let Ok(trans) = self.t.translate(query, context, from, to).await else {
+                continue;
+            };
+            match self.wrong_language(&trans.text, *to) {
+                Some(lang) => detected = lang,
+                None => return (Ok(trans.text), i + 1),
+            }
+        }
+        (Err(detected), self.retries.len())
+    }
+
+    /// Translates and reports all segments in the wrong language instead of failing
+    pub async fn translate_vec_with_report(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<(TranslationListOutput, LanguageReport)> {
+        let mut trans = self
+            .t
+            .translate_vec(query, context.clone(), from, to)
+            .await?;
+        if trans.text.len() != query.len() {
+            return Err(Error::NoResponse.into());
+        }
+        let mut report = LanguageReport::default();
+        for (segment, (query, text)) in query.iter().zip(trans.text.iter_mut()).enumerate() {
+            let Some(detected) = self.wrong_language(text, *to) else {
+                continue;
+            };
+            match self.retry(query, &context, from, to, detected).await {
+                (Ok(retried), _) => {
+                    *text = retried;
+                    report.fixed.push(segment);
+                }
+                (Err(detected), retries) => report.wrong.push(WrongLanguage {
+                    segment,
+                    detected,
+                    retries,
+                }),
+            }
+        }
+        Ok((trans, report))
+    }
+
+    fn check(&self, report: &LanguageReport) -> Result<(), Error> {
+        if self.strict && !report.wrong.is_empty() {
+            return Err(Error::WrongLanguage(
+                report.wrong.iter().map(|v| v.segment).collect(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T: AsyncTranslator + Send + Sync, D: Detector + Send + Sync> AsyncTranslator
+    for LanguageCheck<T, D>
+{
+    fn local(&self) -> bool {
+        self.t.local()
+    }
+
+    fn limits(&self) -> Limits {
+        self.t.limits()
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        self.t.supports(from, to)
+    }
+
+    async fn translate(
+        &self,
+        query: &str,
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        let mut trans = self.t.translate(query, context.clone(), from, to).await?;
+        let mut report = LanguageReport::default();
+        if let Some(detected) = self.wrong_language(&trans.text, *to) {
+            match self.retry(query, &context, from, to, detected).await {
+                (Ok(retried), _) => trans.text = retried,
+                (Err(detected), retries) => report.wrong.push(WrongLanguage {
+                    segment: 0,
+                    detected,
+                    retries,
+                }),
+            }
+        }
+        self.check(&report)?;
+        Ok(trans)
+    }
+
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        let (trans, report) = self
+            .translate_vec_with_report(query, context, from, to)
+            .await?;
+        self.check(&report)?;
+        Ok(trans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Echoes the query unless `from` is given, like a model stuck in the source language
+    struct EchoWithoutSource;
+
+    #[async_trait]
+    impl AsyncTranslator for EchoWithoutSource {
+        fn local(&self) -> bool {
+            true
+        }
+
+        async fn translate(
+            &self,
+            query: &str,
+            _: Option<PromptBuilder>,
+            from: Option<Language>,
+            _: &Language,
+        ) -> anyhow::Result<TranslationOutput> {
+            let text = match from {
+                Some(_) => format!("de:{query}"),
+                None => query.to_owned(),
+            };
+            Ok(TranslationOutput { text, lang: None })
+        }
+
+        async fn translate_vec(
+            &self,
+            query: &[String],
+            _: Option<PromptBuilder>,
+            from: Option<Language>,
+            to: &Language,
+        ) -> anyhow::Result<TranslationListOutput> {
+            let mut text = vec![];
+            for q in query {
+                text.push(self.translate(q, None, from, to).await?.text);
+            }
+            Ok(TranslationListOutput { text, lang: None })
+        }
+    }
+
+    /// Text starting with `de:` is german, anything else english
+    struct Prefix;
+
+    impl Detector for Prefix {
+        fn detect_language(&self, text: &str) -> Option<Language> {
+            match text.starts_with("de:") {
+                true => Some(Language::German),
+                false => Some(Language::English),
+            }
+        }
+    }
+
+    /// Guesses english for everything without being sure
+    struct Unsure;
+
+    impl Detector for Unsure {
+        fn detect_language(&self, _: &str) -> Option<Language> {
+            Some(Language::English)
+        }
+
+        fn detect_ranked(&self, _: &str) -> Vec<(Language, f64)> {
+            vec![(Language::English, 0.3)]
+        }
+    }
+
+    fn query() -> Vec<String> {
+        vec!["Hello".to_owned(), "42!".to_owned()]
+    }
+
+    #[tokio::test]
+    async fn retries_wrong_language() {
+        let t = LanguageCheck::new(
+            EchoWithoutSource,
+            Prefix,
+            vec![Retry::DetectSource],
+            0.0,
+            true,
+        );
+        let (trans, report) = t
+            .translate_vec_with_report(&query(), None, None, &Language::German)
+            .await
+            .unwrap();
+        // numbers and punctuation have no language
+        assert_eq!(trans.text, vec!["de:Hello", "42!"]);
+        assert_eq!(report.fixed, vec![0]);
+        assert!(report.wrong.is_empty());
+    }
+
+    #[tokio::test]
+    async fn flags_wrong_language() {
+        let t = LanguageCheck::new(
+            EchoWithoutSource,
+            Prefix,
+            vec![Retry::AutoSource],
+            0.0,
+            false,
+        );
+        let (trans, report) = t
+            .translate_vec_with_report(&query(), None, None, &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(trans.text, query());
+        assert_eq!(
+            report.wrong,
+            vec![WrongLanguage {
+                segment: 0,
+                detected: Language::English,
+                retries: 1,
+            }]
+        );
+
+        let strict = LanguageCheck::new(EchoWithoutSource, Prefix, vec![], 0.0, true);
+        let err = strict
+            .translate("Hello", None, None, &Language::German)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::WrongLanguage(v)) if v == &vec![0]
+        ));
+    }
+
+    #[tokio::test]
+    async fn ignores_unsure_detections() {
+        let t = LanguageCheck::new(EchoWithoutSource, Unsure, vec![], 0.5, true);
+        let (trans, report) = t
+            .translate_vec_with_report(&query(), None, None, &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(trans.text, query());
+        assert!(report.wrong.is_empty() && report.fixed.is_empty());
+
+        let trusting = LanguageCheck::new(EchoWithoutSource, Unsure, vec![], 0.0, false);
+        let (_, report) = trusting
+            .translate_vec_with_report(&query(), None, None, &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(report.wrong.len(), 1);
+    }
+}
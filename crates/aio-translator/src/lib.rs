@@ -1,15 +1,39 @@
+mod auto_detect;
+mod chunk;
+mod circuit_breaker;
+mod cjk_detector;
+pub mod detector_eval;
+mod ensemble;
+mod ensemble_detector;
+mod glossary;
+mod hedge;
+mod key_pool;
+mod language_check;
+mod mask;
+mod metrics;
+mod multi;
+mod pivot;
+mod placeholder;
+mod priority;
+pub mod quality_eval;
 mod rate_limit;
+mod router;
 mod style_transfer;
+#[cfg(test)]
+mod test_support;
+mod translation_memory;
 
 pub use aio_translator_interface::{
-    AsyncTranslator, Detector, Language, Model, TranslationListOutput, TranslationOutput,
-    error::ApiError, error::Error, prompt::PromptBuilder,
+    AsyncDetector, AsyncTranslator, Detector, Language, Model, TranslationListOutput,
+    TranslationOutput, error::ApiError, error::Error, prompt::PromptBuilder,
 };
 
-pub use aio_translator_baidu::BaiduTranslator;
+pub use aio_translator_baidu::{BaiduDetector, BaiduTranslator};
 pub use aio_translator_caiyun::CaiyunTranslator;
 pub use aio_translator_deepl::DeeplTranslator;
-pub use aio_translator_google::GoogleTranslator;
+#[cfg(feature = "fasttext")]
+pub use aio_translator_fasttext::FastTextDetector;
+pub use aio_translator_google::{GoogleDetector, GoogleTranslator};
 pub use aio_translator_jparacrawl::JParaCrawlTranslator;
 pub use aio_translator_jparacrawl::Size as JParaCrawlSize;
 pub use aio_translator_langid::LangIdDetector;
@@ -23,15 +47,46 @@ pub use aio_translator_nllb::NLLBTranslator;
 pub use aio_translator_nllb::Size as NLLBSize;
 pub use aio_translator_none::NoneTranslator;
 pub use aio_translator_original::OriginalTranslator;
-pub use aio_translator_papago::PapagoTranslator;
+pub use aio_translator_papago::{PapagoDetector, PapagoTranslator};
 pub use aio_translator_sugoi::SugoiTranslator;
 #[cfg(feature = "whatlang")]
 pub use aio_translator_whatlang::WhatLangDetector;
 pub use aio_translator_youdao::YoudaoTranslator;
 pub use ct2rs::ComputeType;
 pub mod wrapper {
-    pub use crate::rate_limit::RateLimiter;
+    pub use crate::auto_detect::AutoDetect;
+    pub use crate::chunk::Chunker;
+    pub use crate::circuit_breaker::CircuitBreaker;
+    pub use crate::ensemble::Ensemble;
+    pub use crate::glossary::GlossaryEnforcer;
+    pub use crate::hedge::Hedged;
+    pub use crate::key_pool::KeyPool;
+    pub use crate::language_check::LanguageCheck;
+    pub use crate::pivot::Pivot;
+    pub use crate::placeholder::PlaceholderProtect;
+    pub use crate::rate_limit::{Lane, RateLimiter};
+    pub use crate::router::Router;
     pub use crate::style_transfer::StyleTransfer;
+    pub use crate::translation_memory::MemoryLookup;
 }
 
+pub use circuit_breaker::CircuitState;
+pub use cjk_detector::CjkDetector;
+pub use ensemble::{Agreement, Candidate, EnsembleOutput, EnsembleSegment, LengthRatio, Scorer};
+pub use ensemble_detector::{EnsembleDetector, Voter};
+pub use glossary::{Glossary, GlossaryReport, GlossaryTerm, LostTerm};
+pub use hedge::HedgeStats;
+pub use key_pool::KeyStatus;
+pub use language_check::{LanguageReport, Retry, WrongLanguage};
+pub use metrics::{chrf, corpus_bleu, corpus_chrf_pp};
+pub use multi::{MultiOutput, SourceDetection, TranslateMulti};
+pub use pivot::Hop;
+pub use placeholder::{
+    IcuMessage, PlaceholderGrammar, PlaceholderIssue, PlaceholderReport, RegexPlaceholder, brace,
+    default_grammars, double_brace, markup, printf,
+};
+pub use priority::{LaneStats, Priority};
+pub use rate_limit::Budget;
+pub use translation_memory::{TmMatch, TranslationMemory};
+
 pub use style_transfer::is_valuable_text;
@@ -0,0 +1,82 @@
+use std::ops::Range;
+
+use fancy_regex::Regex;
+
+fn sentinel_regex() -> Regex {
+    Regex::new(r"⟦\s*(\d+)\s*⟧").unwrap()
+}
+
+/// Byte ranges of the sentinels already in `text`
+pub(crate) fn sentinel_spans(text: &str) -> Vec<Range<usize>> {
+    sentinel_regex()
+        .find_iter(text)
+        .flatten()
+        .map(|m| m.range())
+        .collect()
+}
+
+/// Token that stands in for the `i`th protected span while the text is translated
+pub(crate) fn sentinel(i: usize) -> String {
+    format!("⟦{i}⟧")
+}
+
+/// First sentinel index that is not used in `text` yet.
+/// Lets stacked wrappers mask the same text without mixing up their sentinels.
+pub(crate) fn next_free(text: &str) -> usize {
+    sentinel_regex()
+        .captures_iter(text)
+        .flatten()
+        .filter_map(|caps| caps[1].parse::<usize>().ok())
+        .max()
+        .map(|v| v + 1)
+        .unwrap_or_default()
+}
+
+/// Replaces the sentinels `offset..offset + spans.len()` in `text` with their span.
+/// Returns the restored text and how often each sentinel was found.
+/// Translators sometimes add spaces inside the brackets, those are accepted as well.
+pub(crate) fn unmask(text: &str, offset: usize, spans: &[String]) -> (String, Vec<usize>) {
+    let mut counts = vec![0; spans.len()];
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for caps in sentinel_regex().captures_iter(text).flatten() {
+        let m = caps.get(0).expect("group 0");
+        let Some(i) = caps[1]
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| i.checked_sub(offset))
+            .filter(|i| *i < spans.len())
+        else {
+            continue;
+        };
+        out.push_str(&text[last..m.start()]);
+        out.push_str(&spans[i]);
+        counts[i] += 1;
+        last = m.end();
+    }
+    out.push_str(&text[last..]);
+    (out, counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unmask_spaced_sentinels() {
+        let spans = vec!["Alice".to_owned(), "Bob".to_owned()];
+        let text = format!("{} meets ⟦ 1 ⟧ and ⟦7⟧", sentinel(0));
+        let (out, counts) = unmask(&text, 0, &spans);
+        assert_eq!(out, "Alice meets Bob and ⟦7⟧");
+        assert_eq!(counts, vec![1, 1]);
+    }
+
+    #[test]
+    fn unmask_with_offset() {
+        let text = format!("{} {} {}", sentinel(0), sentinel(2), sentinel(3));
+        assert_eq!(next_free(&text), 4);
+        let (out, counts) = unmask(&text, 2, &["a".to_owned(), "b".to_owned()]);
+        assert_eq!(out, format!("{} a b", sentinel(0)));
+        assert_eq!(counts, vec![1, 1]);
+    }
+}
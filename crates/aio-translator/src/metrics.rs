@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use aio_translator_interface::detect::{is_han, is_kana};
+
+/// Character n-gram orders of chrF
+const CHAR_ORDER: usize = 6;
+/// chrF weighs recall twice as much as precision
+const BETA: f64 = 2.0;
+
+/// Word n-gram orders chrF++ adds
+const WORD_ORDER: usize = 2;
+/// N-gram orders of BLEU
+const BLEU_ORDER: usize = 4;
+
+/// N-grams of one order in the hypothesis, the reference and in both, summed over a corpus
+#[derive(Clone, Copy, Debug, Default)]
+struct NgramStats {
+    hyp: usize,
+    reference: usize,
+    matches: usize,
+}
+
+impl NgramStats {
+    fn of<T: Eq + std::hash::Hash>(hyp: &[T], reference: &[T], n: usize) -> Self {
+        let hyp_grams = ngrams(hyp, n);
+        let ref_grams = ngrams(reference, n);
+        Self {
+            hyp: hyp_grams.values().sum(),
+            reference: ref_grams.values().sum(),
+            matches: hyp_grams
+                .iter()
+                .map(|(gram, count)| (*count).min(ref_grams.get(gram).copied().unwrap_or(0)))
+                .sum(),
+        }
+    }
+
+    fn add(&mut self, other: Self) {
+        self.hyp += other.hyp;
+        self.reference += other.reference;
+        self.matches += other.matches;
+    }
+}
+
+fn non_whitespace(text: &str) -> Vec<char> {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn char_stats(hypothesis: &str, reference: &str) -> Vec<NgramStats> {
+    let (hyp, refs) = (non_whitespace(hypothesis), non_whitespace(reference));
+    (1..=CHAR_ORDER)
+        .map(|n| NgramStats::of(&hyp, &refs, n))
+        .collect()
+}
+
+/// Words are split like for BLEU, so text without spaces still has word n-grams
+fn word_stats(hypothesis: &str, reference: &str) -> Vec<NgramStats> {
+    let (hyp, refs) = (word_tokens(hypothesis), word_tokens(reference));
+    (1..=WORD_ORDER)
+        .map(|n| NgramStats::of(&hyp, &refs, n))
+        .collect()
+}
+
+/// chrF from 0 to 100 over the averaged precision and recall of every order both sides have n-grams of
+fn chrf_score(stats: &[NgramStats]) -> f64 {
+    let mut precision = 0.0;
+    let mut recall = 0.0;
+    let mut orders = 0;
+    for v in stats.iter().filter(|v| v.hyp > 0 && v.reference > 0) {
+        precision += v.matches as f64 / v.hyp as f64;
+        recall += v.matches as f64 / v.reference as f64;
+        orders += 1;
+    }
+    if orders == 0 {
+        return 0.0;
+    }
+    f_score(precision / orders as f64, recall / orders as f64) * 100.0
+}
+
+/// Sentence level chrF between `hypothesis` and `reference`, from 0 to 100.
+/// Whitespace is ignored like in sacreBLEU.
+pub fn chrf(hypothesis: &str, reference: &str) -> f64 {
+    if hypothesis.trim().is_empty() && reference.trim().is_empty() {
+        return 100.0;
+    }
+    chrf_score(&char_stats(hypothesis, reference))
+}
+
+/// Corpus level chrF++ from 0 to 100, chrF with word uni- and bigrams added.
+/// The n-gram counts of all sentences are summed before scoring, hypotheses and references pair up by index.
+/// Panics if there aren't as many hypotheses as references.
+pub fn corpus_chrf_pp<H: AsRef<str>, R: AsRef<str>>(hypotheses: &[H], references: &[R]) -> f64 {
+    assert_eq!(
+        hypotheses.len(),
+        references.len(),
+        "every hypothesis needs a reference"
+    );
+    let mut stats = vec![NgramStats::default(); CHAR_ORDER + WORD_ORDER];
+    for (hyp, reference) in hypotheses.iter().zip(references) {
+        let (hyp, reference) = (hyp.as_ref(), reference.as_ref());
+        let sentence = char_stats(hyp, reference)
+            .into_iter()
+            .chain(word_stats(hyp, reference));
+        for (total, v) in stats.iter_mut().zip(sentence) {
+            total.add(v);
+        }
+    }
+    chrf_score(&stats)
+}
+
+/// Han and kana are written without spaces, BLEU and chrF++ count each of them as a word
+fn is_cjk(c: char) -> bool {
+    is_kana(c) || is_han(c)
+}
+
+/// Splits on whitespace and splits off punctuation and CJK characters as their own tokens
+fn word_tokens(text: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() && !is_cjk(c) {
+            word.push(c);
+            continue;
+        }
+        if !word.is_empty() {
+            tokens.push(std::mem::take(&mut word));
+        }
+        if !c.is_whitespace() {
+            tokens.push(c.to_string());
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+/// Corpus level BLEU from 0 to 100 with 4-grams and a brevity penalty.
+/// Orders without matches are smoothed like sacreBLEU's default `exp` method, hypotheses and references pair up by index.
+/// Panics if there aren't as many hypotheses as references.
+pub fn corpus_bleu<H: AsRef<str>, R: AsRef<str>>(hypotheses: &[H], references: &[R]) -> f64 {
+    assert_eq!(
+        hypotheses.len(),
+        references.len(),
+        "every hypothesis needs a reference"
+    );
+    let mut stats = [NgramStats::default(); BLEU_ORDER];
+    for (hyp, reference) in hypotheses.iter().zip(references) {
+        let hyp = word_tokens(hyp.as_ref());
+        let refs = word_tokens(reference.as_ref());
+        for (n, total) in stats.iter_mut().enumerate() {
+            total.add(NgramStats::of(&hyp, &refs, n + 1));
+        }
+    }
+    // no n-grams of an order means the hypotheses are too short to score
+    if stats.iter().any(|v| v.hyp == 0) {
+        return 0.0;
+    }
+    let mut smoothing = 1.0;
+    let mut log_precision = 0.0;
+    for v in &stats {
+        let precision = match v.matches {
+            0 => {
+                smoothing *= 2.0;
+                1.0 / (smoothing * v.hyp as f64)
+            }
+            matches => matches as f64 / v.hyp as f64,
+        };
+        log_precision += precision.ln() / BLEU_ORDER as f64;
+    }
+    let (hyp_len, ref_len) = (stats[0].hyp, stats[0].reference);
+    let brevity_penalty = match hyp_len < ref_len {
+        true => (1.0 - ref_len as f64 / hyp_len as f64).exp(),
+        false => 1.0,
+    };
+    brevity_penalty * log_precision.exp() * 100.0
+}
+
+fn ngrams<T: Eq + std::hash::Hash>(items: &[T], n: usize) -> HashMap<&[T], usize> {
+    let mut out = HashMap::new();
+    for gram in items.windows(n) {
+        *out.entry(gram).or_default() += 1;
+    }
+    out
+}
+
+fn f_score(precision: f64, recall: f64) -> f64 {
+    let beta2 = BETA * BETA;
+    let denominator = beta2 * precision + recall;
+    if denominator == 0.0 {
+        return 0.0;
+    }
+    (1.0 + beta2) * precision * recall / denominator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chrf_bounds() {
+        assert_eq!(chrf("the cat sat", "the cat sat"), 100.0);
+        assert_eq!(chrf("abc", "xyz"), 0.0);
+        let close = chrf("the cat sat", "the cat sat down");
+        let far = chrf("a dog ran", "the cat sat down");
+        assert!(close > 60.0 && far < close, "{close} {far}");
+    }
+
+    #[test]
+    fn bleu() {
+        let refs = ["the cat is on the mat", "a b c d e"];
+        assert_eq!(corpus_bleu(&refs, &refs), 100.0);
+        // 1-grams 10/11, 2-grams 7/9, 3-grams 4/7, 4-grams 2/5, same length
+        let hyps = ["the cat sat on the mat", "a b c d e"];
+        let expected = ((10.0f64 / 11.0).ln()
+            + (7.0f64 / 9.0).ln()
+            + (4.0f64 / 7.0).ln()
+            + (2.0f64 / 5.0).ln())
+            / 4.0;
+        assert!((corpus_bleu(&hyps, &refs) - expected.exp() * 100.0).abs() < 1e-9);
+        // shorter hypotheses are penalized
+        assert!(corpus_bleu(&["a b c d"], &["a b c d e"]) < 100.0);
+        // 1/4, 1/(2 * 3), 1/(4 * 2), 1/(8 * 1)
+        let smoothed = (0.25f64 * (1.0 / 6.0) * (1.0 / 8.0) * (1.0 / 8.0)).powf(0.25) * 100.0;
+        assert!((corpus_bleu(&["a y z w"], &["a b c d"]) - smoothed).abs() < 1e-9);
+        assert_eq!(corpus_bleu(&["a b c"], &["a b c"]), 0.0);
+        assert_eq!(
+            word_tokens("猫が好き, really!"),
+            ["猫", "が", "好", "き", ",", "really", "!"]
+        );
+    }
+
+    #[test]
+    fn chrf_pp() {
+        let refs = ["the cat sat down", "猫が座った"];
+        assert_eq!(corpus_chrf_pp(&refs, &refs), 100.0);
+        let close = corpus_chrf_pp(&["the cat sat", "猫が座った"], &refs);
+        let far = corpus_chrf_pp(&["a dog ran", "犬が走った"], &refs);
+        assert!(close > far && close < 100.0, "{close} {far}");
+        // words of unspaced text still count
+        let stats = word_stats("猫が好き", "猫が嫌い");
+        assert_eq!((stats[0].hyp, stats[0].matches), (4, 2));
+        assert_eq!((stats[1].hyp, stats[1].matches), (3, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "every hypothesis needs a reference")]
+    fn length_mismatch() {
+        corpus_bleu(&["a b c d"], &["a b c d", "e f g h"]);
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use aio_translator_interface::{
+    AsyncDetector, AsyncTranslator, Language, TranslationListOutput, prompt::PromptBuilder,
+};
+use async_trait::async_trait;
+use futures::future::join_all;
+use tokio::sync::Semaphore;
+
+/// Targets translated at the same time by [`TranslateMulti::translate_multi`]
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Where the source language of a [`MultiOutput`] came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SourceDetection {
+    /// Passed in by the caller
+    Given,
+    /// Detected once by the detector
+    Detector,
+    /// Reported by the translation into the first target
+    FirstTarget,
+    /// Nothing told the language, every target detected the source on its own
+    Failed,
+}
+
+/// Translations of the same text into several languages
+#[derive(Debug)]
+pub struct MultiOutput {
+    /// Source language as given or detected, see `detection`
+    pub from: Option<Language>,
+    pub detection: SourceDetection,
+    /// Every target has its own result, one failing target doesn't fail the others
+    pub results: HashMap<Language, anyhow::Result<TranslationListOutput>>,
+}
+
+/// Translates into several target languages, implemented for every [`AsyncTranslator`].
+/// Requests go through the translator as usual, so wrappers like the `RateLimiter` or a `MemoryLookup` still apply.
+#[async_trait]
+pub trait TranslateMulti: AsyncTranslator {
+    async fn translate_multi(
+        &self,
+        query: &[String],
+        from: Option<Language>,
+        targets: &[Language],
+    ) -> MultiOutput {
+        self.translate_multi_with(query, None, from, targets, DEFAULT_CONCURRENCY, None)
+            .await
+    }
+
+    /// Translates into every target with at most `concurrency` requests at a time.
+    /// Without `from` the source is detected once with `detector`. If there is none or it can't tell,
+    /// the first target is translated alone and the language it reports is reused for the rest.
+    async fn translate_multi_with(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        targets: &[Language],
+        concurrency: usize,
+        detector: Option<&dyn AsyncDetector>,
+    ) -> MultiOutput {
+        let mut remaining = vec![];
+        for target in targets {
+            if !remaining.contains(target) {
+                remaining.push(*target);
+            }
+        }
+
+        let mut from = from;
+        let mut detection = SourceDetection::Given;
+        if from.is_none() {
+            detection = SourceDetection::Failed;
+            if let Some(detector) = detector {
+                from = detector.detect(&query.join("\n")).await.ok().flatten();
+                if from.is_some() {
+                    detection = SourceDetection::Detector;
+                }
+            }
+        }
+
+        let mut results = HashMap::new();
+        if from.is_none() && !remaining.is_empty() {
+            let first = remaining.remove(0);
+            let r = self
+                .translate_vec(query, context.clone(), None, &first)
+                .await;
+            // some apis report the target instead of the source
+            from = r.as_ref().ok().and_then(|v| v.lang).filter(|v| *v != first);
+            if from.is_some() {
+                detection = SourceDetection::FirstTarget;
+            }
+            results.insert(first, r);
+        }
+
+        let semaphore = Semaphore::new(concurrency.max(1));
+        let outputs = join_all(remaining.iter().map(|to| {
+            let context = context.clone();
+            let semaphore = &semaphore;
+            async move {
+                let _permit = semaphore.acquire().await;
+                self.translate_vec(query, context, from, to).await
+            }
+        }))
+        .await;
+        results.extend(remaining.into_iter().zip(outputs));
+        MultiOutput {
+            from,
+            detection,
+            results,
+        }
+    }
+}
+
+impl<T: AsyncTranslator + ?Sized> TranslateMulti for T {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+    use crate::{cjk_detector::CjkDetector, test_support::Fake};
+
+    const TARGETS: [Language; 5] = [
+        Language::English,
+        Language::German,
+        Language::French,
+        Language::Spanish,
+        Language::German,
+    ];
+
+    #[tokio::test]
+    async fn detect_once_and_fan_out() {
+        let t = Fake::tagging()
+            .detecting(Language::Japanese)
+            .with_latency(5);
+        let out = t
+            .translate_multi_with(&["猫".to_owned()], None, None, &TARGETS, 2, None)
+            .await;
+        assert_eq!(out.from, Some(Language::Japanese));
+        assert_eq!(out.detection, SourceDetection::FirstTarget);
+        assert_eq!(out.results.len(), 4);
+        let de = out.results[&Language::German].as_ref().unwrap();
+        assert_eq!(de.text, vec!["猫|ja-de"]);
+
+        let sources = t.sources.lock().unwrap();
+        assert_eq!(sources[0], None);
+        assert!(sources[1..].iter().all(|v| *v == Some(Language::Japanese)));
+        assert_eq!(t.peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn detector_decides_up_front() {
+        // reports no language, like the offline models
+        let t = Fake::tagging().with_latency(5);
+        let detector = CjkDetector::standalone();
+        let out = t
+            .translate_multi_with(
+                &["猫が好き".to_owned()],
+                None,
+                None,
+                &TARGETS,
+                2,
+                Some(&detector),
+            )
+            .await;
+        assert_eq!(
+            (out.from, out.detection),
+            (Some(Language::Japanese), SourceDetection::Detector)
+        );
+        let en = out.results[&Language::English].as_ref().unwrap();
+        assert_eq!(en.text, vec!["猫が好き|ja-en"]);
+        assert!(
+            t.sources
+                .lock()
+                .unwrap()
+                .iter()
+                .all(|v| *v == Some(Language::Japanese))
+        );
+
+        let t = Fake::tagging();
+        let out = t.translate_multi(&["猫".to_owned()], None, &TARGETS).await;
+        assert_eq!((out.from, out.detection), (None, SourceDetection::Failed));
+        assert!(t.sources.lock().unwrap().iter().all(|v| v.is_none()));
+    }
+}
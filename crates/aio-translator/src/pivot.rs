@@ -0,0 +1,155 @@
+use aio_translator_interface::{
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, chunk::Limits,
+    prompt::PromptBuilder,
+};
+use async_trait::async_trait;
+
+/// One step of a pivot translation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hop {
+    /// Source language as given or reported by the translator
+    pub from: Option<Language>,
+    pub to: Language,
+    pub text: Vec<String>,
+}
+
+/// Translates through an intermediate language with two translators, e.g. ja→en→de.
+/// Pairs starting or ending in the intermediate language only take one hop.
+pub struct Pivot<A: AsyncTranslator, B: AsyncTranslator> {
+    first: A,
+    second: B,
+    via: Language,
+}
+
+impl<A: AsyncTranslator, B: AsyncTranslator> Pivot<A, B> {
+    /// Create a new Pivot translator
+    /// - `first`: translates from the source into `via`
+    /// - `second`: translates from `via` into the target
+    /// - `via`: intermediate language, usually English
+    pub fn new(first: A, second: B, via: Language) -> Self {
+        Self { first, second, via }
+    }
+
+    /// Translates and returns the output of every hop
+    pub async fn translate_vec_hops(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<(TranslationListOutput, Vec<Hop>)> {
+        let mut hops = vec![];
+        let mut text = query.to_vec();
+        let mut lang = from;
+        if from != Some(self.via) {
+            let trans = self
+                .first
+                .translate_vec(&text, context.clone(), from, &self.via)
+                .await?;
+            lang = trans.lang.or(from);
+            hops.push(Hop {
+                from: lang,
+                to: self.via,
+                text: trans.text.clone(),
+            });
+            text = trans.text;
+        }
+        if *to != self.via {
+            let trans = self
+                .second
+                .translate_vec(&text, context, Some(self.via), to)
+                .await?;
+            hops.push(Hop {
+                from: Some(self.via),
+                to: *to,
+                text: trans.text.clone(),
+            });
+            text = trans.text;
+        }
+        Ok((TranslationListOutput { text, lang }, hops))
+    }
+}
+
+#[async_trait]
+impl<A, B> AsyncTranslator for Pivot<A, B>
+where
+    A: AsyncTranslator + Send + Sync,
+    B: AsyncTranslator + Send + Sync,
+{
+    fn local(&self) -> bool {
+        self.first.local() && self.second.local()
+    }
+
+    fn limits(&self) -> Limits {
+        self.first.limits().strictest(self.second.limits())
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        let first = from == Some(self.via) || self.first.supports(from, &self.via);
+        let second = *to == self.via || self.second.supports(Some(self.via), to);
+        first && second
+    }
+
+    async fn translate(
+        &self,
+        query: &str,
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        let (mut trans, _) = self
+            .translate_vec_hops(&[query.to_owned()], context, from, to)
+            .await?;
+        Ok(TranslationOutput {
+            text: trans.text.remove(0),
+            lang: trans.lang,
+        })
+    }
+
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        let (trans, _) = self.translate_vec_hops(query, context, from, to).await?;
+        Ok(trans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Fake;
+
+    #[tokio::test]
+    async fn two_hops() {
+        let t = Pivot::new(
+            Fake::pair(Language::Japanese, Language::English),
+            Fake::pair(Language::English, Language::German),
+            Language::English,
+        );
+        assert!(t.supports(Some(Language::Japanese), &Language::German));
+        assert!(t.supports(Some(Language::English), &Language::German));
+        assert!(!t.supports(Some(Language::French), &Language::German));
+
+        let query = vec!["猫".to_owned()];
+        let (out, hops) = t
+            .translate_vec_hops(&query, None, Some(Language::Japanese), &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(out.text, vec!["猫|ja-en|en-de"]);
+        assert_eq!(out.lang, Some(Language::Japanese));
+        assert_eq!(hops.len(), 2);
+        assert_eq!(hops[0].text, vec!["猫|ja-en"]);
+        assert_eq!(hops[1].from, Some(Language::English));
+
+        let (out, hops) = t
+            .translate_vec_hops(&query, None, Some(Language::Japanese), &Language::English)
+            .await
+            .unwrap();
+        assert_eq!(out.text, vec!["猫|ja-en"]);
+        assert_eq!(hops.len(), 1);
+    }
+}
@@ -0,0 +1,435 @@
+use std::ops::Range;
+
+use aio_translator_interface::{
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, chunk::Limits,
+    error::Error, prompt::PromptBuilder,
+};
+use async_trait::async_trait;
+use fancy_regex::Regex;
+
+use crate::mask::{next_free, sentinel, unmask};
+
+/// Finds spans that must survive the translation unchanged
+pub trait PlaceholderGrammar: Send + Sync {
+    /// Byte ranges of all protected spans in `text`
+    fn find(&self, text: &str) -> Vec<Range<usize>>;
+}
+
+/// Protects every match of a regex
+pub struct RegexPlaceholder(pub Regex);
+
+impl PlaceholderGrammar for RegexPlaceholder {
+    fn find(&self, text: &str) -> Vec<Range<usize>> {
+        self.0
+            .find_iter(text)
+            .flatten()
+            .map(|m| m.range())
+            .collect()
+    }
+}
+
+/// `{name}` and `{0}`
+pub fn brace() -> RegexPlaceholder {
+    RegexPlaceholder(Regex::new(r"\{[\w.-]+\}").unwrap())
+}
+
+/// `{{count}}`
+pub fn double_brace() -> RegexPlaceholder {
+    RegexPlaceholder(Regex::new(r"\{\{[^{}]+\}\}").unwrap())
+}
+
+/// printf style `%s`, `%1$s`, `%.2f` and `%%`.
+/// The space flag is left out, it would turn percentages in prose like `50% off` into placeholders.
+pub fn printf() -> RegexPlaceholder {
+    RegexPlaceholder(
+        Regex::new(
+            r"%(\d+\$)?[-+0#]*(\d+|\*)?(\.(\d+|\*))?(hh|h|ll|l|L|z|j|t)?[diouxXeEfFgGaAcspn%@]",
+        )
+        .unwrap(),
+    )
+}
+
+/// Opening, closing and self closing tags like `<b>`, `</b>` and `<br/>`. The text between tags is translated.
+pub fn markup() -> RegexPlaceholder {
+    RegexPlaceholder(Regex::new(r#"</?[A-Za-z][\w-]*(\s+[^<>]*)?/?>"#).unwrap())
+}
+
+/// ICU `plural`, `select` and `selectordinal` messages.
+/// Protects the argument, the case selectors, `#` and the braces while the case messages are translated.
+pub struct IcuMessage;
+
+impl PlaceholderGrammar for IcuMessage {
+    fn find(&self, text: &str) -> Vec<Range<usize>> {
+        let header = Regex::new(r"\{\s*[\w.-]+\s*,\s*(plural|select|selectordinal)\s*,").unwrap();
+        let mut ranges = vec![];
+        for m in header.find_iter(text).flatten() {
+            ranges.push(m.range());
+            icu_cases(text, m.end(), &mut ranges);
+        }
+        ranges
+    }
+}
+
+fn icu_cases(text: &str, mut pos: usize, ranges: &mut Vec<Range<usize>>) {
+    let skip_whitespace = |pos: usize| {
+        text[pos..]
+            .find(|c: char| !c.is_whitespace())
+            .map(|v| pos + v)
+    };
+    loop {
+        let Some(start) = skip_whitespace(pos) else {
+            return;
+        };
+        if text[start..].starts_with('}') {
+            ranges.push(start..start + 1);
+            return;
+        }
+        let Some(selector_end) = text[start..]
+            .find(|c: char| c.is_whitespace() || c == '{' || c == '}')
+            .map(|v| start + v)
+        else {
+            return;
+        };
+        if text[start..selector_end].starts_with("offset:") {
+            ranges.push(start..selector_end);
+            pos = selector_end;
+            continue;
+        }
+        let Some(open) = skip_whitespace(selector_end).filter(|v| text[*v..].starts_with('{'))
+        else {
+            return;
+        };
+        ranges.push(start..open + 1);
+
+        let mut depth = 1;
+        let mut close = None;
+        for (i, c) in text[open + 1..].char_indices() {
+            let i = open + 1 + i;
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = Some(i);
+                        break;
+                    }
+                }
+                '#' if depth == 1 => ranges.push(i..i + 1),
+                _ => {}
+            }
+        }
+        let Some(close) = close else {
+            return;
+        };
+        ranges.push(close..close + 1);
+        pos = close + 1;
+    }
+}
+
+/// Braces, double braces, printf, markup and ICU messages
+pub fn default_grammars() -> Vec<Box<dyn PlaceholderGrammar>> {
+    vec![
+        Box::new(IcuMessage),
+        Box::new(double_brace()),
+        Box::new(brace()),
+        Box::new(printf()),
+        Box::new(markup()),
+    ]
+}
+
+/// A placeholder that is missing or duplicated in the translation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlaceholderIssue {
+    /// Index of the segment in the query
+    pub segment: usize,
+    pub placeholder: String,
+    /// How often the placeholder was found in the translation
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PlaceholderReport {
+    pub issues: Vec<PlaceholderIssue>,
+}
+
+/// Replaces placeholders and markup with sentinels before the translation and restores them afterwards
+pub struct PlaceholderProtect<T: AsyncTranslator> {
+    t: T,
+    grammars: Vec<Box<dyn PlaceholderGrammar>>,
+    /// Fail with [`Error::PlaceholderMismatch`] instead of returning a translation with broken placeholders
+    strict: bool,
+}
+
+impl<T: AsyncTranslator> PlaceholderProtect<T> {
+    /// Create a new PlaceholderProtect wrapper
+    /// - `grammars`: placeholder syntaxes to protect, see [`default_grammars`]
+    /// - `strict`: return [`Error::PlaceholderMismatch`] if a placeholder went missing or was duplicated
+    pub fn new(t: T, grammars: Vec<Box<dyn PlaceholderGrammar>>, strict: bool) -> Self {
+        Self {
+            t,
+            grammars,
+            strict,
+        }
+    }
+
+    /// Masked text, index of the first sentinel and the protected spans
+    fn mask(&self, text: &str) -> (String, usize, Vec<String>) {
+        let mut ranges = self
+            .grammars
+            .iter()
+            .flat_map(|g| g.find(text))
+            .filter(|r| !r.is_empty())
+            .collect::<Vec<_>>();
+        // earliest and then longest span wins if grammars overlap
+        ranges.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+
+        let offset = next_free(text);
+        let mut out = String::with_capacity(text.len());
+        let mut spans = vec![];
+        let mut last = 0;
+        for range in ranges {
+            if range.start < last {
+                continue;
+            }
+            out.push_str(&text[last..range.start]);
+            out.push_str(&sentinel(offset + spans.len()));
+            spans.push(text[range.clone()].to_owned());
+            last = range.end;
+        }
+        out.push_str(&text[last..]);
+        (out, offset, spans)
+    }
+
+    /// Translates and reports broken placeholders instead of failing
+    pub async fn translate_vec_with_report(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<(TranslationListOutput, PlaceholderReport)> {
+        let (masked, spans): (Vec<_>, Vec<_>) = query
+            .iter()
+            .map(|q| {
+                let (text, offset, spans) = self.mask(q);
+                (text, (offset, spans))
+            })
+            .unzip();
+        let mut trans = self.t.translate_vec(&masked, context, from, to).await?;
+        if trans.text.len() != query.len() {
+            return Err(Error::NoResponse.into());
+        }
+        let mut report = PlaceholderReport::default();
+        for (segment, (text, (offset, spans))) in trans.text.iter_mut().zip(&spans).enumerate() {
+            *text = restore(text, *offset, spans, segment, &mut report);
+        }
+        Ok((trans, report))
+    }
+
+    fn check(&self, report: &PlaceholderReport) -> Result<(), Error> {
+        if self.strict && !report.issues.is_empty() {
+            return Err(Error::PlaceholderMismatch(
+                report
+                    .issues
+                    .iter()
+                    .map(|v| v.placeholder.clone())
+                    .collect(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn restore(
+    text: &str,
+    offset: usize,
+    spans: &[String],
+    segment: usize,
+    report: &mut PlaceholderReport,
+) -> String {
+    let (text, counts) = unmask(text, offset, spans);
+    for (placeholder, count) in spans.iter().zip(counts) {
+        if count != 1 {
+            report.issues.push(PlaceholderIssue {
+                segment,
+                placeholder: placeholder.clone(),
+                count,
+            });
+        }
+    }
+    text
+}
+
+#[async_trait]
+impl<T: AsyncTranslator + Send + Sync> AsyncTranslator for PlaceholderProtect<T> {
+    fn local(&self) -> bool {
+        self.t.local()
+    }
+
+    fn limits(&self) -> Limits {
+        self.t.limits()
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        self.t.supports(from, to)
+    }
+
+    async fn translate(
+        &self,
+        query: &str,
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        let (masked, offset, spans) = self.mask(query);
+        let mut trans = self.t.translate(&masked, context, from, to).await?;
+        let mut report = PlaceholderReport::default();
+        trans.text = restore(&trans.text, offset, &spans, 0, &mut report);
+        self.check(&report)?;
+        Ok(trans)
+    }
+
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        let (trans, report) = self
+            .translate_vec_with_report(query, context, from, to)
+            .await?;
+        self.check(&report)?;
+        Ok(trans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Fake;
+
+    /// Uppercases the text and duplicates the first sentinel
+    struct Shout;
+
+    #[async_trait]
+    impl AsyncTranslator for Shout {
+        fn local(&self) -> bool {
+            true
+        }
+
+        async fn translate(
+            &self,
+            query: &str,
+            _: Option<PromptBuilder>,
+            from: Option<Language>,
+            _: &Language,
+        ) -> anyhow::Result<TranslationOutput> {
+            Ok(TranslationOutput {
+                text: query.to_uppercase(),
+                lang: from,
+            })
+        }
+
+        async fn translate_vec(
+            &self,
+            query: &[String],
+            _: Option<PromptBuilder>,
+            from: Option<Language>,
+            _: &Language,
+        ) -> anyhow::Result<TranslationListOutput> {
+            Ok(TranslationListOutput {
+                text: query
+                    .iter()
+                    .map(|q| format!("{} {}", q.to_uppercase(), sentinel(0)))
+                    .collect(),
+                lang: from,
+            })
+        }
+    }
+
+    fn protect() -> PlaceholderProtect<Shout> {
+        PlaceholderProtect::new(Shout, default_grammars(), true)
+    }
+
+    #[test]
+    fn mask_grammars() {
+        let (masked, offset, spans) =
+            protect().mask("Hi {name}, {{count}} <b>new</b> items for %1$s at 100%%");
+        assert_eq!(offset, 0);
+        assert_eq!(
+            spans,
+            vec!["{name}", "{{count}}", "<b>", "</b>", "%1$s", "%%"]
+        );
+        assert_eq!(masked, "Hi ⟦0⟧, ⟦1⟧ ⟦2⟧new⟦3⟧ items for ⟦4⟧ at 100⟦5⟧");
+    }
+
+    #[test]
+    fn percentages_in_prose() {
+        for text in [
+            "50% off",
+            "100% sure",
+            "10% discount",
+            "save 20% at checkout",
+        ] {
+            let (masked, _, spans) = protect().mask(text);
+            assert_eq!(masked, text);
+            assert!(spans.is_empty());
+        }
+        assert_eq!(protect().mask("%-5d%+.2f").2, vec!["%-5d", "%+.2f"]);
+    }
+
+    #[test]
+    fn mask_icu() {
+        let (masked, _, spans) =
+            protect().mask("{count, plural, =0 {no items} one {# item} other {# items}}");
+        assert_eq!(
+            masked,
+            "⟦0⟧ ⟦1⟧no items⟦2⟧ ⟦3⟧⟦4⟧ item⟦5⟧ ⟦6⟧⟦7⟧ items⟦8⟧⟦9⟧"
+        );
+        assert_eq!(spans[0], "{count, plural,");
+        assert_eq!(spans[3], "one {");
+        assert_eq!(spans[9], "}");
+    }
+
+    #[tokio::test]
+    async fn restore_and_report() {
+        let t = protect();
+        let out = t
+            .translate("hello {name}", None, None, &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(out.text, "HELLO {name}");
+
+        let (out, report) = t
+            .translate_vec_with_report(&["hi {a} {b}".to_owned()], None, None, &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(out.text, vec!["HI {a} {b} {a}".to_owned()]);
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].placeholder, "{a}");
+        assert_eq!(report.issues[0].count, 2);
+        assert!(
+            t.translate_vec(&["hi {a}".to_owned()], None, None, &Language::German)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn segment_count_mismatch() {
+        let query = ["hi {a}".to_owned(), "bye".to_owned()];
+        for answer in [vec!["hallo ⟦0⟧"], vec!["hallo ⟦0⟧", "tschüss", "⟦0⟧"]] {
+            let t =
+                PlaceholderProtect::new(Fake::answering_batch(answer), default_grammars(), false);
+            let err = t
+                .translate_vec_with_report(&query, None, None, &Language::German)
+                .await
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast_ref::<Error>(),
+                Some(Error::NoResponse)
+            ));
+        }
+    }
+}
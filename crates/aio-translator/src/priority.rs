@@ -0,0 +1,215 @@
+use std::{
+    collections::VecDeque,
+    pin::pin,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::Notify;
+
+/// Virtual time a request of a lane with weight 1 costs
+const SCALE: u64 = 1 << 16;
+
+/// Queue lane of a request in the [`RateLimiter`](crate::wrapper::RateLimiter)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Priority {
+    /// Single lines a user is waiting for
+    Interactive,
+    Normal,
+    /// Bulk jobs like whole chapters
+    Batch,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::Interactive, Priority::Normal, Priority::Batch];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn default_weight(self) -> u32 {
+        match self {
+            Priority::Interactive => 8,
+            Priority::Normal => 4,
+            Priority::Batch => 1,
+        }
+    }
+}
+
+/// Queue metrics of one lane
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LaneStats {
+    /// Requests currently waiting
+    pub queued: usize,
+    /// Requests that got through
+    pub granted: u64,
+    pub total_wait: Duration,
+    pub max_wait: Duration,
+}
+
+impl LaneStats {
+    pub fn mean_wait(&self) -> Duration {
+        if self.granted == 0 {
+            return Duration::ZERO;
+        }
+        self.total_wait.div_f64(self.granted as f64)
+    }
+}
+
+struct LaneQueue {
+    weight: u32,
+    /// Tickets in arrival order
+    queue: VecDeque<u64>,
+    /// Start-time fair queuing tag, grows by `SCALE / weight` per request
+    virtual_time: u64,
+    stats: LaneStats,
+}
+
+struct State {
+    lanes: [LaneQueue; 3],
+    /// Virtual time of the last granted request
+    clock: u64,
+    next_ticket: u64,
+    /// A request holds its turn until it passed the rate limits
+    busy: bool,
+}
+
+impl State {
+    /// Non empty lane with the smallest virtual time, higher priority wins ties
+    fn next_lane(&self) -> Option<usize> {
+        (0..self.lanes.len())
+            .filter(|i| !self.lanes[*i].queue.is_empty())
+            .min_by_key(|i| self.lanes[*i].virtual_time)
+    }
+}
+
+/// Weighted fair queue in front of the rate limits.
+/// Every lane gets a share of the turns proportional to its weight, so no lane starves.
+pub(crate) struct Scheduler {
+    state: Mutex<State>,
+    notify: Notify,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(State {
+                lanes: Priority::ALL.map(|p| LaneQueue {
+                    weight: p.default_weight(),
+                    queue: VecDeque::new(),
+                    virtual_time: 0,
+                    stats: LaneStats::default(),
+                }),
+                clock: 0,
+                next_ticket: 0,
+                busy: false,
+            }),
+            notify: Notify::new(),
+        }
+    }
+}
+
+impl Scheduler {
+    pub(crate) fn set_weight(&self, priority: Priority, weight: u32) {
+        self.state.lock().unwrap().lanes[priority.index()].weight = weight.max(1);
+    }
+
+    pub(crate) fn stats(&self, priority: Priority) -> LaneStats {
+        let state = self.state.lock().unwrap();
+        let lane = &state.lanes[priority.index()];
+        LaneStats {
+            queued: lane.queue.len(),
+            ..lane.stats
+        }
+    }
+
+    /// Waits for the turn of a request in `priority`. Others wait until the returned [`Turn`] is dropped.
+    pub(crate) async fn turn(&self, priority: Priority) -> Turn<'_> {
+        let lane = priority.index();
+        let enqueued = Instant::now();
+        let ticket = {
+            let mut state = self.state.lock().unwrap();
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            let clock = state.clock;
+            let queued = &mut state.lanes[lane];
+            if queued.queue.is_empty() {
+                // idle lanes don't save up credit
+                queued.virtual_time = queued.virtual_time.max(clock);
+            }
+            queued.queue.push_back(ticket);
+            ticket
+        };
+        let mut waiting = Waiting {
+            scheduler: self,
+            lane,
+            ticket: Some(ticket),
+        };
+
+        loop {
+            let mut notified = pin!(self.notify.notified());
+            notified.as_mut().enable();
+            {
+                let mut state = self.state.lock().unwrap();
+                if !state.busy
+                    && state.next_lane() == Some(lane)
+                    && state.lanes[lane].queue.front() == Some(&ticket)
+                {
+                    state.busy = true;
+                    state.clock = state.lanes[lane].virtual_time;
+                    let granted = &mut state.lanes[lane];
+                    granted.queue.pop_front();
+                    granted.virtual_time += SCALE / granted.weight as u64;
+                    waiting.ticket = None;
+                    return Turn {
+                        scheduler: self,
+                        lane,
+                        enqueued,
+                    };
+                }
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Removes the ticket if the waiting request is cancelled
+struct Waiting<'a> {
+    scheduler: &'a Scheduler,
+    lane: usize,
+    ticket: Option<u64>,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        let Some(ticket) = self.ticket else {
+            return;
+        };
+        self.scheduler.state.lock().unwrap().lanes[self.lane]
+            .queue
+            .retain(|v| *v != ticket);
+        self.scheduler.notify.notify_waiters();
+    }
+}
+
+/// The turn of a request, passes it on to the next lane on drop
+pub(crate) struct Turn<'a> {
+    scheduler: &'a Scheduler,
+    lane: usize,
+    enqueued: Instant,
+}
+
+impl Drop for Turn<'_> {
+    fn drop(&mut self) {
+        let wait = self.enqueued.elapsed();
+        {
+            let mut state = self.scheduler.state.lock().unwrap();
+            state.busy = false;
+            let stats = &mut state.lanes[self.lane].stats;
+            stats.granted += 1;
+            stats.total_wait += wait;
+            stats.max_wait = stats.max_wait.max(wait);
+        }
+        self.scheduler.notify.notify_waiters();
+    }
+}
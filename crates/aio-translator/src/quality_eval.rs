@@ -0,0 +1,241 @@
+use std::{fmt::Write as _, path::Path, time::Instant};
+
+use aio_translator_interface::{AsyncTranslator, Language};
+use serde::Serialize;
+
+use crate::metrics::{corpus_bleu, corpus_chrf_pp};
+
+/// Source segment with its reference translation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParallelSample {
+    pub source: String,
+    pub reference: String,
+}
+
+/// Reads a test set with one `source<TAB>reference` pair per line.
+/// Empty lines and lines starting with `#` are skipped.
+pub fn read_parallel_tsv(data: &str) -> anyhow::Result<Vec<ParallelSample>> {
+    let mut samples = vec![];
+    for (i, line) in data.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (source, reference) = line
+            .split_once('\t')
+            .ok_or_else(|| anyhow::anyhow!("line {} has no tab after the source", i + 1))?;
+        samples.push(ParallelSample {
+            source: source.to_owned(),
+            reference: reference.to_owned(),
+        });
+    }
+    Ok(samples)
+}
+
+pub fn load_parallel_tsv<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<ParallelSample>> {
+    read_parallel_tsv(&std::fs::read_to_string(path)?)
+}
+
+/// Pairs up line `n` of `source` with line `n` of `reference`, both need the same number of lines
+pub fn read_parallel_lines(source: &str, reference: &str) -> anyhow::Result<Vec<ParallelSample>> {
+    let (sources, references) = (
+        source.lines().collect::<Vec<_>>(),
+        reference.lines().collect::<Vec<_>>(),
+    );
+    if sources.len() != references.len() {
+        anyhow::bail!(
+            "{} source lines but {} reference lines",
+            sources.len(),
+            references.len()
+        );
+    }
+    Ok(sources
+        .into_iter()
+        .zip(references)
+        .map(|(source, reference)| ParallelSample {
+            source: source.to_owned(),
+            reference: reference.to_owned(),
+        })
+        .collect())
+}
+
+pub fn load_parallel_files<P: AsRef<Path>, R: AsRef<Path>>(
+    source: P,
+    reference: R,
+) -> anyhow::Result<Vec<ParallelSample>> {
+    read_parallel_lines(
+        &std::fs::read_to_string(source)?,
+        &std::fs::read_to_string(reference)?,
+    )
+}
+
+/// Scores of one translator over a test set
+#[derive(Clone, Debug, Serialize)]
+pub struct QualityReport {
+    pub name: String,
+    pub segments: usize,
+    /// Segments of batches that failed, scored as empty translations
+    pub failed: usize,
+    pub bleu: f64,
+    pub chrf_pp: f64,
+    /// Time spent translating
+    pub seconds: f64,
+}
+
+/// Translates the test set `batch` segments at a time and scores the output against the references.
+/// Failed batches don't abort the run, they count as empty translations.
+pub async fn evaluate_translator<T: AsyncTranslator + ?Sized>(
+    name: impl Into<String>,
+    t: &T,
+    samples: &[ParallelSample],
+    from: Option<Language>,
+    to: &Language,
+    batch: usize,
+) -> QualityReport {
+    let mut hypotheses = Vec::with_capacity(samples.len());
+    let mut failed = 0;
+    let start = Instant::now();
+    for chunk in samples.chunks(batch.max(1)) {
+        let query = chunk.iter().map(|v| v.source.clone()).collect::<Vec<_>>();
+        match t.translate_vec(&query, None, from, to).await {
+            Ok(trans) if trans.text.len() == query.len() => hypotheses.extend(trans.text),
+            _ => {
+                failed += query.len();
+                hypotheses.extend(std::iter::repeat_n(String::new(), query.len()));
+            }
+        }
+    }
+    let seconds = start.elapsed().as_secs_f64();
+    let references = samples.iter().map(|v| &v.reference).collect::<Vec<_>>();
+    QualityReport {
+        name: name.into(),
+        segments: samples.len(),
+        failed,
+        bleu: corpus_bleu(&hypotheses, &references),
+        chrf_pp: corpus_chrf_pp(&hypotheses, &references),
+        seconds,
+    }
+}
+
+/// One row per translator with its scores
+pub fn comparison_markdown(reports: &[QualityReport]) -> String {
+    let mut out =
+        "| translator | segments | failed | BLEU | chrF++ | seconds |\n|---|---:|---:|---:|---:|---:|\n"
+            .to_owned();
+    for report in reports {
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {:.2} | {:.2} | {:.1} |",
+            report.name,
+            report.segments,
+            report.failed,
+            report.bleu,
+            report.chrf_pp,
+            report.seconds
+        );
+    }
+    out
+}
+
+pub fn comparison_json(reports: &[QualityReport]) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use aio_translator_interface::{
+        TranslationListOutput, TranslationOutput, error::Error, prompt::PromptBuilder,
+    };
+    use async_trait::async_trait;
+
+    use super::*;
+
+    /// Echoes the query, fails batches containing `fail`
+    struct Echo;
+
+    #[async_trait]
+    impl AsyncTranslator for Echo {
+        fn local(&self) -> bool {
+            true
+        }
+
+        async fn translate(
+            &self,
+            query: &str,
+            _: Option<PromptBuilder>,
+            _: Option<Language>,
+            _: &Language,
+        ) -> anyhow::Result<TranslationOutput> {
+            Ok(TranslationOutput {
+                text: query.to_owned(),
+                lang: None,
+            })
+        }
+
+        async fn translate_vec(
+            &self,
+            query: &[String],
+            _: Option<PromptBuilder>,
+            _: Option<Language>,
+            _: &Language,
+        ) -> anyhow::Result<TranslationListOutput> {
+            if query.iter().any(|v| v == "fail") {
+                Err(Error::NoResponse)?
+            }
+            Ok(TranslationListOutput {
+                text: query.to_vec(),
+                lang: None,
+            })
+        }
+    }
+
+    const TEST_SET: &str = "# source\treference
+the cat sat on the mat\tthe cat is on the mat
+
+a b c d e\ta b c d e";
+
+    #[test]
+    fn read_test_set() {
+        let samples = read_parallel_tsv(TEST_SET).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[1].reference, "a b c d e");
+        assert!(read_parallel_tsv("no tab").is_err());
+
+        let lines = read_parallel_lines("a\nb\n", "x\ny").unwrap();
+        assert_eq!(lines[1].source, "b");
+        assert_eq!(lines[1].reference, "y");
+        assert!(read_parallel_lines("a\nb", "x").is_err());
+    }
+
+    #[tokio::test]
+    async fn evaluate() {
+        let samples = read_parallel_tsv(TEST_SET).unwrap();
+        let report =
+            evaluate_translator("echo", &Echo, &samples, None, &Language::English, 1).await;
+        assert_eq!((report.segments, report.failed), (2, 0));
+        assert!(report.bleu > 0.0 && report.bleu < 100.0);
+        assert_eq!(
+            report.bleu,
+            corpus_bleu(
+                &["the cat sat on the mat", "a b c d e"],
+                &["the cat is on the mat", "a b c d e"]
+            )
+        );
+
+        let mut failing = samples.clone();
+        failing.push(ParallelSample {
+            source: "fail".to_owned(),
+            reference: "fail".to_owned(),
+        });
+        let failed =
+            evaluate_translator("fail", &Echo, &failing, None, &Language::English, 2).await;
+        assert_eq!(failed.failed, 1);
+        assert!(failed.chrf_pp < report.chrf_pp);
+
+        let markdown = comparison_markdown(&[report.clone(), failed]);
+        assert!(markdown.contains("| echo | 2 | 0 |"));
+        assert!(markdown.contains("| fail | 3 | 1 |"));
+        let json = comparison_json(&[report]).unwrap();
+        assert!(json.contains("\"name\": \"echo\""));
+        assert!(json.contains("\"chrf_pp\""));
+    }
+}
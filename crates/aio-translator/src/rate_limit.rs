@@ -5,21 +5,113 @@ use std::{
 };
 
 use aio_translator_interface::{
-    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, prompt::PromptBuilder,
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, chunk::Limits,
+    error::Error, prompt::PromptBuilder,
 };
 use async_trait::async_trait;
 use tokio::sync::{Mutex, Semaphore, SemaphorePermit};
 
+use crate::priority::{LaneStats, Priority, Scheduler};
+
+/// How often a request is retried after the api answered with a rate limit
+const MAX_RETRIES: u32 = 3;
+
+/// Requests and characters allowed within a sliding window.
+/// Clones share the same budget, so translators using the same api key can coordinate.
+#[derive(Clone)]
+pub struct Budget {
+    state: Arc<Mutex<BudgetState>>,
+    max_requests: Option<usize>,
+    max_chars: Option<usize>,
+    window: Duration,
+}
+
+#[derive(Default)]
+struct BudgetState {
+    /// Time and character count of the requests in the window
+    used: VecDeque<(Instant, usize)>,
+    /// Set after the api rate limited a request
+    paused_until: Option<Instant>,
+}
+
+impl Budget {
+    /// Create a new Budget
+    /// - `max_requests`: Some(n) to allow at most n requests within `window`
+    /// - `max_chars`: Some(n) to allow at most n characters within `window`
+    pub fn new(max_requests: Option<usize>, max_chars: Option<usize>, window: Duration) -> Self {
+        Self {
+            state: Default::default(),
+            max_requests,
+            max_chars,
+            window,
+        }
+    }
+
+    /// Waits until a request of `chars` characters fits into the budget and books it.
+    /// A request larger than the whole character budget is let through once the window is empty.
+    pub async fn acquire(&self, chars: usize) {
+        loop {
+            let mut state = self.state.lock().await;
+            let now = Instant::now();
+            while let Some(&(front, _)) = state.used.front() {
+                if now.duration_since(front) >= self.window {
+                    state.used.pop_front();
+                } else {
+                    break;
+                }
+            }
+
+            let wait = match state.paused_until.filter(|v| *v > now) {
+                Some(until) => until - now,
+                None => {
+                    let used_chars = state.used.iter().map(|v| v.1).sum::<usize>();
+                    let requests_ok = self.max_requests.is_none_or(|v| state.used.len() < v);
+                    let chars_ok = self
+                        .max_chars
+                        .is_none_or(|v| used_chars + chars <= v || state.used.is_empty());
+                    if requests_ok && chars_ok {
+                        state.used.push_back((now, chars));
+                        return;
+                    }
+                    state.used.front().map_or(self.window, |(oldest, _)| {
+                        self.window.saturating_sub(now.duration_since(*oldest))
+                    })
+                }
+            };
+            drop(state);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Stops everyone sharing the budget for `duration`, e.g. after a `429` with `Retry-After`
+    pub async fn pause(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut state = self.state.lock().await;
+        state.paused_until = Some(state.paused_until.map_or(until, |v| v.max(until)));
+    }
+
+    /// Requests and characters used in the current window
+    pub async fn usage(&self) -> (usize, usize) {
+        let state = self.state.lock().await;
+        let now = Instant::now();
+        state
+            .used
+            .iter()
+            .filter(|(t, _)| now.duration_since(*t) < self.window)
+            .fold((0, 0), |(requests, chars), (_, c)| {
+                (requests + 1, chars + c)
+            })
+    }
+}
+
 pub struct RateLimiter<T: AsyncTranslator> {
     t: T,
     /// Max concurrency limiter
     semaphore: Option<Arc<Semaphore>>,
-    ///  rate limiter queue
-    timestamps: Option<Arc<Mutex<VecDeque<Instant>>>>,
-    /// Max requests within time window
-    max_requests: usize,
-    /// Time window for rate limiting
-    window: Duration,
+    /// Request and character budget, possibly shared with other translators
+    budget: Option<Budget>,
+    /// Decides which lane goes next when requests have to wait
+    scheduler: Scheduler,
 }
 
 impl<T: AsyncTranslator> RateLimiter<T> {
@@ -31,57 +123,119 @@ impl<T: AsyncTranslator> RateLimiter<T> {
         concurrent_limit: Option<usize>,
         max_requests: Option<(usize, Duration)>,
     ) -> Self {
-        let semaphore = concurrent_limit.map(|n| Arc::new(Semaphore::new(n)));
-        let (max_requests, window, timestamps) = if let Some((n, d)) = max_requests {
-            (n, d, Some(Arc::new(Mutex::new(VecDeque::with_capacity(n)))))
-        } else {
-            (usize::MAX, Duration::ZERO, None)
-        };
+        let budget = max_requests.map(|(n, d)| Budget::new(Some(n), None, d));
+        Self::with_budget(t, concurrent_limit, budget)
+    }
 
+    /// Create a new RateLimiter wrapper with a [`Budget`] that can be shared with other translators
+    /// - `concurrent_limit`: Some(n) to allow up to n concurrent requests
+    /// - `budget`: requests and characters allowed within a window
+    pub fn with_budget(t: T, concurrent_limit: Option<usize>, budget: Option<Budget>) -> Self {
+        let semaphore = concurrent_limit.map(|n| Arc::new(Semaphore::new(n)));
         Self {
             t,
             semaphore,
-            timestamps,
-            max_requests,
-            window,
+            budget,
+            scheduler: Scheduler::default(),
         }
     }
 
-    /// Internal function to enforce concurrency + rate limits
-    async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
-        if let Some(timestamps) = &self.timestamps {
-            loop {
-                let mut queue = timestamps.lock().await;
-                let now = Instant::now();
-
-                while let Some(&front) = queue.front() {
-                    if now.duration_since(front) > self.window {
-                        queue.pop_front();
-                    } else {
-                        break;
-                    }
-                }
+    pub fn budget(&self) -> Option<&Budget> {
+        self.budget.as_ref()
+    }
 
-                if queue.len() < self.max_requests {
-                    queue.push_back(now);
-                    break;
-                }
+    /// Share of the turns `priority` gets relative to the other lanes, defaults are 8, 4 and 1
+    pub fn set_weight(&self, priority: Priority, weight: u32) {
+        self.scheduler.set_weight(priority, weight);
+    }
 
-                if let Some(&oldest) = queue.front() {
-                    let wait_time = self.window.saturating_sub(now.duration_since(oldest));
-                    drop(queue);
-                    tokio::time::sleep(wait_time).await;
-                }
-            }
+    /// Queue depth and wait times of a lane
+    pub fn lane_stats(&self, priority: Priority) -> LaneStats {
+        self.scheduler.stats(priority)
+    }
+
+    /// Translator that queues its requests in the `priority` lane.
+    /// Using the RateLimiter directly queues in [`Priority::Normal`].
+    pub fn lane(&self, priority: Priority) -> Lane<'_, T> {
+        Lane {
+            limiter: self,
+            priority,
         }
+    }
 
-        let _permit = if let Some(sem) = &self.semaphore {
-            Some(sem.acquire().await.expect("Semaphore closed"))
-        } else {
-            None
+    /// Internal function to enforce concurrency + rate limits
+    async fn acquire(&self, priority: Priority, chars: usize) -> Option<SemaphorePermit<'_>> {
+        if self.budget.is_none() && self.semaphore.is_none() {
+            return None;
+        }
+        let turn = self.scheduler.turn(priority).await;
+        if let Some(budget) = &self.budget {
+            budget.acquire(chars).await;
+        }
+        let permit = match &self.semaphore {
+            Some(sem) => Some(sem.acquire().await.expect("Semaphore closed")),
+            None => None,
         };
+        drop(turn);
+        permit
+    }
 
-        _permit
+    /// Retries requests the api rejected with [`Error::RateLimited`], waiting for `Retry-After` or backing off exponentially
+    async fn with_retries<R, F>(
+        &self,
+        priority: Priority,
+        chars: usize,
+        f: impl Fn() -> F,
+    ) -> anyhow::Result<R>
+    where
+        F: Future<Output = anyhow::Result<R>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let permit = self.acquire(priority, chars).await;
+            let r = f().await;
+            drop(permit);
+            let retry_after = match r.as_ref().err().and_then(|e| e.downcast_ref::<Error>()) {
+                Some(Error::RateLimited(retry_after)) if attempt < MAX_RETRIES => *retry_after,
+                _ => return r,
+            };
+            let wait = retry_after.unwrap_or(Duration::from_secs(1 << attempt));
+            match &self.budget {
+                Some(budget) => budget.pause(wait).await,
+                None => tokio::time::sleep(wait).await,
+            }
+            attempt += 1;
+        }
+    }
+
+    async fn translate_in(
+        &self,
+        priority: Priority,
+        query: &str,
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        let chars = query.chars().count();
+        self.with_retries(priority, chars, || {
+            self.t.translate(query, context.clone(), from, to)
+        })
+        .await
+    }
+
+    async fn translate_vec_in(
+        &self,
+        priority: Priority,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        let chars = query.iter().map(|v| v.chars().count()).sum();
+        self.with_retries(priority, chars, || {
+            self.t.translate_vec(query, context.clone(), from, to)
+        })
+        .await
     }
 }
 
@@ -91,6 +245,57 @@ impl<T: AsyncTranslator + Send + Sync> AsyncTranslator for RateLimiter<T> {
         self.t.local()
     }
 
+    fn limits(&self) -> Limits {
+        self.t.limits()
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        self.t.supports(from, to)
+    }
+
+    async fn translate(
+        &self,
+        query: &str,
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        self.translate_in(Priority::Normal, query, context, from, to)
+            .await
+    }
+
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        self.translate_vec_in(Priority::Normal, query, context, from, to)
+            .await
+    }
+}
+
+/// Requests through a [`RateLimiter`] in one priority lane
+pub struct Lane<'a, T: AsyncTranslator> {
+    limiter: &'a RateLimiter<T>,
+    priority: Priority,
+}
+
+#[async_trait]
+impl<T: AsyncTranslator + Send + Sync> AsyncTranslator for Lane<'_, T> {
+    fn local(&self) -> bool {
+        self.limiter.local()
+    }
+
+    fn limits(&self) -> Limits {
+        self.limiter.limits()
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        self.limiter.supports(from, to)
+    }
+
     async fn translate(
         &self,
         query: &str,
@@ -98,10 +303,9 @@ impl<T: AsyncTranslator + Send + Sync> AsyncTranslator for RateLimiter<T> {
         from: Option<Language>,
         to: &Language,
     ) -> anyhow::Result<TranslationOutput> {
-        let permit = self.acquire().await;
-        let r = self.t.translate(query, context, from, to).await;
-        drop(permit);
-        r
+        self.limiter
+            .translate_in(self.priority, query, context, from, to)
+            .await
     }
 
     async fn translate_vec(
@@ -111,9 +315,106 @@ impl<T: AsyncTranslator + Send + Sync> AsyncTranslator for RateLimiter<T> {
         from: Option<Language>,
         to: &Language,
     ) -> anyhow::Result<TranslationListOutput> {
-        let permit = self.acquire().await;
-        let r = self.t.translate_vec(query, context, from, to).await;
-        drop(permit);
-        r
+        self.limiter
+            .translate_vec_in(self.priority, query, context, from, to)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use tokio::task::JoinSet;
+
+    use super::*;
+    use crate::test_support::Fake;
+
+    fn queued<T: AsyncTranslator>(t: &RateLimiter<T>) -> usize {
+        Priority::ALL.iter().map(|p| t.lane_stats(*p).queued).sum()
+    }
+
+    /// Queues the requests in order while another request holds the turn, then lets them through.
+    /// Returns the order they reached the api in.
+    async fn run(requests: Vec<(Priority, String)>) -> (Arc<RateLimiter<Fake>>, Vec<String>) {
+        let t = Arc::new(RateLimiter::new(Fake::echo(), Some(1), None));
+        let held = t.scheduler.turn(Priority::Normal).await;
+        let mut set = JoinSet::new();
+        for (i, (priority, query)) in requests.into_iter().enumerate() {
+            let limiter = t.clone();
+            set.spawn(async move {
+                limiter
+                    .lane(priority)
+                    .translate(&query, None, None, &Language::German)
+                    .await
+                    .unwrap();
+            });
+            while queued(&t) <= i {
+                tokio::task::yield_now().await;
+            }
+        }
+        drop(held);
+        set.join_all().await;
+        let order = t.t.queries.lock().unwrap().clone();
+        (t, order)
+    }
+
+    #[tokio::test]
+    async fn interactive_overtakes_batch() {
+        let requests = [
+            (Priority::Batch, "b0"),
+            (Priority::Batch, "b1"),
+            (Priority::Batch, "b2"),
+            (Priority::Interactive, "i0"),
+            (Priority::Interactive, "i1"),
+        ];
+        let (t, order) = run(requests.map(|(p, v)| (p, v.to_owned())).to_vec()).await;
+        // ties go to the higher priority, a batch request costs as much as 8 interactive ones
+        assert_eq!(order, vec!["i0", "b0", "i1", "b1", "b2"]);
+        let stats = t.lane_stats(Priority::Interactive);
+        assert_eq!((stats.queued, stats.granted), (0, 2));
+        assert!(stats.max_wait >= stats.mean_wait());
+    }
+
+    #[tokio::test]
+    async fn batch_does_not_starve() {
+        let mut requests = vec![(Priority::Batch, "b0".to_owned())];
+        requests.extend((0..18).map(|i| (Priority::Interactive, format!("i{i}"))));
+        requests.push((Priority::Batch, "b1".to_owned()));
+        let (t, order) = run(requests).await;
+        // b0 and i0 to i8 take as much virtual time as b1 waited for
+        let position = order.iter().position(|v| v == "b1").unwrap();
+        assert_eq!(position, 10, "{order:?}");
+        assert_eq!(t.lane_stats(Priority::Batch).granted, 2);
+        assert_eq!(t.lane_stats(Priority::Interactive).granted, 18);
+    }
+
+    #[tokio::test]
+    async fn shared_char_budget() {
+        let budget = Budget::new(Some(10), Some(10), Duration::from_millis(100));
+        let other = budget.clone();
+        let start = Instant::now();
+        budget.acquire(6).await;
+        assert_eq!(other.usage().await, (1, 6));
+        other.acquire(6).await;
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert_eq!(budget.usage().await, (1, 6));
+    }
+
+    #[tokio::test]
+    async fn retry_after_rate_limit() {
+        let budget = Budget::new(None, Some(1000), Duration::from_secs(1));
+        let throttled =
+            Fake::echo().fail_next(|| Error::RateLimited(Some(Duration::from_millis(50))));
+        let t = RateLimiter::with_budget(throttled, None, Some(budget.clone()));
+        let start = Instant::now();
+        let out = t
+            .translate("hello", None, None, &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(out.text, "hello");
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert_eq!(t.t.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(budget.usage().await, (2, 10));
     }
 }
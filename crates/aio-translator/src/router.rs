@@ -0,0 +1,181 @@
+use aio_translator_interface::{
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, chunk::Limits,
+    error::Error, prompt::PromptBuilder,
+};
+use async_trait::async_trait;
+
+/// Sends each request to the first translator that supports the language pair.
+/// Put direct translators first and [`Pivot`](crate::wrapper::Pivot)s last,
+/// so pivoting only happens for pairs no direct translator covers.
+/// Translators behind an open [`CircuitBreaker`](crate::wrapper::CircuitBreaker) are skipped.
+pub struct Router {
+    translators: Vec<Box<dyn AsyncTranslator + Send + Sync>>,
+}
+
+impl Router {
+    /// Create a new Router
+    /// - `translators`: candidates in order of preference
+    pub fn new(translators: Vec<Box<dyn AsyncTranslator + Send + Sync>>) -> Self {
+        Self { translators }
+    }
+
+    /// Index of the translator that handles `from` → `to`
+    pub fn route(&self, from: Option<Language>, to: &Language) -> Option<usize> {
+        self.translators.iter().position(|v| v.supports(from, to))
+    }
+
+    /// Translators that handle `from` → `to` in order of preference
+    fn candidates(
+        &self,
+        from: Option<Language>,
+        to: &Language,
+    ) -> impl Iterator<Item = &dyn AsyncTranslator> {
+        self.translators
+            .iter()
+            .filter(move |v| v.supports(from, to))
+            .map(|v| &**v as &dyn AsyncTranslator)
+    }
+}
+
+/// A circuit can open between `supports` and the request, the next candidate is tried then
+fn is_circuit_open(e: &anyhow::Error) -> bool {
+    matches!(e.downcast_ref::<Error>(), Some(Error::CircuitOpen))
+}
+
+#[async_trait]
+impl AsyncTranslator for Router {
+    fn local(&self) -> bool {
+        self.translators.iter().all(|v| v.local())
+    }
+
+    /// Strictest limits of all translators, the route is only known per request
+    fn limits(&self) -> Limits {
+        self.translators
+            .iter()
+            .map(|v| v.limits())
+            .reduce(Limits::strictest)
+            .unwrap_or_default()
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        self.route(from, to).is_some()
+    }
+
+    async fn translate(
+        &self,
+        query: &str,
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        let mut error = None;
+        for t in self.candidates(from, to) {
+            match t.translate(query, context.clone(), from, to).await {
+                Err(e) if is_circuit_open(&e) => error = Some(e),
+                r => return r,
+            }
+        }
+        Err(error.unwrap_or_else(|| Error::UnknownLanguageGroup(from, *to).into()))
+    }
+
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        let mut error = None;
+        for t in self.candidates(from, to) {
+            match t.translate_vec(query, context.clone(), from, to).await {
+                Err(e) if is_circuit_open(&e) => error = Some(e),
+                r => return r,
+            }
+        }
+        Err(error.unwrap_or_else(|| Error::UnknownLanguageGroup(from, *to).into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        test_support::Fake,
+        wrapper::{CircuitBreaker, Pivot},
+    };
+
+    #[tokio::test]
+    async fn pivot_when_no_direct_route() {
+        let t = Router::new(vec![
+            Box::new(Fake::pair(Language::English, Language::German)),
+            Box::new(Pivot::new(
+                Fake::pair(Language::Japanese, Language::English),
+                Fake::pair(Language::English, Language::German),
+                Language::English,
+            )),
+        ]);
+        assert_eq!(t.route(Some(Language::English), &Language::German), Some(0));
+        assert_eq!(
+            t.route(Some(Language::Japanese), &Language::German),
+            Some(1)
+        );
+        assert_eq!(t.route(Some(Language::Japanese), &Language::French), None);
+
+        let query = vec!["猫".to_owned()];
+        let out = t
+            .translate_vec(&query, None, Some(Language::Japanese), &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(out.text, vec!["猫|ja-en|en-de"]);
+        let err = t
+            .translate_vec(&query, None, Some(Language::Japanese), &Language::French)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::UnknownLanguageGroup(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn skip_open_circuit() {
+        let broken = CircuitBreaker::new(
+            Fake::echo().failing(|| Error::RequestFailed(503)),
+            1,
+            Duration::from_secs(10),
+            Duration::from_secs(10),
+        );
+        assert!(
+            broken
+                .translate("a", None, None, &Language::German)
+                .await
+                .is_err()
+        );
+        let t = Router::new(vec![Box::new(broken), Box::new(Fake::tagging())]);
+        assert_eq!(t.route(None, &Language::German), Some(1));
+        let out = t
+            .translate_vec(&["猫".to_owned()], None, None, &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(out.text, vec!["猫|auto-de"]);
+
+        // the circuit opened after `supports` was asked
+        let t = Router::new(vec![
+            Box::new(Fake::echo().failing(|| Error::CircuitOpen)),
+            Box::new(Fake::tagging()),
+        ]);
+        let out = t
+            .translate("猫", None, None, &Language::German)
+            .await
+            .unwrap();
+        assert_eq!(out.text, "猫|auto-de");
+        let t = Router::new(vec![Box::new(Fake::echo().failing(|| Error::CircuitOpen))]);
+        let err = t
+            .translate("猫", None, None, &Language::German)
+            .await
+            .unwrap_err();
+        assert!(is_circuit_open(&err));
+    }
+}
@@ -1,5 +1,6 @@
 use aio_translator_interface::{
-    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, prompt::PromptBuilder,
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, chunk::Limits,
+    prompt::PromptBuilder,
 };
 use async_trait::async_trait;
 use fancy_regex::Regex;
@@ -14,6 +15,14 @@ impl<T: AsyncTranslator + Send + Sync> AsyncTranslator for StyleTransfer<T> {
     fn local(&self) -> bool {
         self.t.local()
     }
+
+    fn limits(&self) -> Limits {
+        self.t.limits()
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        from == Some(*to) || self.t.supports(from, to)
+    }
     async fn translate(
         &self,
         query: &str,
@@ -0,0 +1,219 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use aio_translator_interface::{
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, chunk::Limits,
+    error::Error, prompt::PromptBuilder,
+};
+use async_trait::async_trait;
+
+/// What the [`Fake`] answers for a segment
+#[derive(Default)]
+pub(crate) enum Reply {
+    /// The segment itself
+    #[default]
+    Echo,
+    /// The same text for every segment
+    Text(&'static str),
+    /// `texts[i]` for the `i`th segment of a request
+    Texts(Vec<&'static str>),
+    /// The segment tagged with the language pair, e.g. `猫|ja-en`
+    Tag,
+    /// These segments for every request, however many were sent
+    Batch(Vec<&'static str>),
+}
+
+/// Translator for the wrapper tests, configured with the builder methods.
+/// Records every call.
+#[derive(Default)]
+pub(crate) struct Fake {
+    reply: Reply,
+    latency: Duration,
+    limits: Limits,
+    /// Language reported as detected when no source is given
+    detects: Option<Language>,
+    /// The only pair supported, others fail with [`Error::UnknownLanguageGroup`]
+    pair: Option<(Language, Language)>,
+    /// Error of every call while set
+    fail: Mutex<Option<fn() -> Error>>,
+    /// Errors returned by the next calls, one per call
+    failures: Mutex<VecDeque<fn() -> Error>>,
+    pub calls: AtomicUsize,
+    /// Query segments in the order they arrived
+    pub queries: Mutex<Vec<String>>,
+    /// Source language of every call
+    pub sources: Mutex<Vec<Option<Language>>>,
+    running: AtomicUsize,
+    /// Most calls running at the same time
+    pub peak: AtomicUsize,
+}
+
+impl Fake {
+    pub fn echo() -> Self {
+        Self::default()
+    }
+
+    pub fn answering(text: &'static str) -> Self {
+        Self {
+            reply: Reply::Text(text),
+            ..Default::default()
+        }
+    }
+
+    /// Tags the segments with the language pair
+    pub fn tagging() -> Self {
+        Self {
+            reply: Reply::Tag,
+            ..Default::default()
+        }
+    }
+
+    /// Only translates `from` → `to` and tags the segments with the pair
+    pub fn pair(from: Language, to: Language) -> Self {
+        Self {
+            reply: Reply::Tag,
+            pair: Some((from, to)),
+            ..Default::default()
+        }
+    }
+
+    /// Answers the `i`th segment of every request with `texts[i]`
+    pub fn answering_each(texts: Vec<&'static str>) -> Self {
+        Self {
+            reply: Reply::Texts(texts),
+            ..Default::default()
+        }
+    }
+
+    /// Answers every request with `texts`, to test answers with the wrong number of segments
+    pub fn answering_batch(texts: Vec<&'static str>) -> Self {
+        Self {
+            reply: Reply::Batch(texts),
+            ..Default::default()
+        }
+    }
+
+    /// Reports `lang` as detected source language
+    pub fn detecting(mut self, lang: Language) -> Self {
+        self.detects = Some(lang);
+        self
+    }
+
+    /// Answers or fails after `millis`
+    pub fn with_latency(mut self, millis: u64) -> Self {
+        self.latency = Duration::from_millis(millis);
+        self
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Fails every call with `error`
+    pub fn failing(self, error: fn() -> Error) -> Self {
+        self.set_failing(Some(error));
+        self
+    }
+
+    /// Starts or stops failing every call
+    pub fn set_failing(&self, error: Option<fn() -> Error>) {
+        *self.fail.lock().unwrap() = error;
+    }
+
+    /// Fails the next call not failed by an earlier `fail_next`
+    pub fn fail_next(self, error: fn() -> Error) -> Self {
+        self.failures.lock().unwrap().push_back(error);
+        self
+    }
+
+    async fn answer(
+        &self,
+        query: &[String],
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<Vec<String>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.queries.lock().unwrap().extend_from_slice(query);
+        self.sources.lock().unwrap().push(from);
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(running, Ordering::SeqCst);
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
+        self.running.fetch_sub(1, Ordering::SeqCst);
+        if !self.supports(from, to) {
+            return Err(Error::UnknownLanguageGroup(from, *to).into());
+        }
+        let failure = self.failures.lock().unwrap().pop_front();
+        if let Some(error) = failure.or(*self.fail.lock().unwrap()) {
+            return Err(error().into());
+        }
+        if let Reply::Batch(texts) = &self.reply {
+            return Ok(texts.iter().map(|v| v.to_string()).collect());
+        }
+        Ok(query
+            .iter()
+            .enumerate()
+            .map(|(i, q)| match &self.reply {
+                Reply::Echo => q.clone(),
+                Reply::Text(text) => text.to_string(),
+                Reply::Texts(texts) => texts.get(i).copied().unwrap_or_default().to_owned(),
+                Reply::Tag => {
+                    let tag = |v: Option<Language>| v.and_then(|v| v.to_tag()).unwrap_or("auto");
+                    format!("{q}|{}-{}", tag(from), tag(Some(*to)))
+                }
+                Reply::Batch(_) => unreachable!(),
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl AsyncTranslator for Fake {
+    fn local(&self) -> bool {
+        false
+    }
+
+    fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        self.pair
+            .is_none_or(|pair| (from, *to) == (Some(pair.0), pair.1))
+    }
+
+    async fn translate(
+        &self,
+        query: &str,
+        _: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        let mut text = self.answer(&[query.to_owned()], from, to).await?;
+        Ok(TranslationOutput {
+            text: text.remove(0),
+            lang: from.or(self.detects),
+        })
+    }
+
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        _: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        Ok(TranslationListOutput {
+            text: self.answer(query, from, to).await?,
+            lang: from.or(self.detects),
+        })
+    }
+}
@@ -0,0 +1,585 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use aio_translator_interface::{
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, chunk::Limits,
+    error::Error, prompt::PromptBuilder,
+};
+use anyhow::bail;
+use async_trait::async_trait;
+use quick_xml::{
+    Reader, Writer,
+    escape::resolve_predefined_entity,
+    events::{BytesDecl, BytesStart, BytesText, Event},
+};
+
+/// A translation memory hit
+#[derive(Clone, Debug, PartialEq)]
+pub struct TmMatch {
+    pub source: String,
+    pub target: String,
+    /// Similarity between the query and `source`. 1.0 is an exact match
+    pub score: f64,
+}
+
+/// Previously reviewed translations grouped by language pair
+#[derive(Clone, Default)]
+pub struct TranslationMemory {
+    entries: HashMap<(Language, Language), BTreeMap<String, String>>,
+}
+
+impl TranslationMemory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a translation. An existing entry with the same source is replaced
+    pub fn insert(
+        &mut self,
+        from: Language,
+        to: Language,
+        source: impl Into<String>,
+        target: impl Into<String>,
+    ) {
+        self.entries
+            .entry((from, to))
+            .or_default()
+            .insert(source.into(), target.into());
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.values().map(|v| v.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn exact(&self, from: Language, to: Language, query: &str) -> Option<&str> {
+        self.entries
+            .get(&(from, to))?
+            .get(query)
+            .map(|v| v.as_str())
+    }
+
+    /// All entries with a similarity of at least `threshold`, best first
+    pub fn fuzzy(
+        &self,
+        from: Language,
+        to: Language,
+        query: &str,
+        threshold: f64,
+        limit: usize,
+    ) -> Vec<TmMatch> {
+        let Some(entries) = self.entries.get(&(from, to)) else {
+            return vec![];
+        };
+        let query_chars = query.chars().collect::<Vec<_>>();
+        let mut matches = entries
+            .iter()
+            .filter_map(|(source, target)| {
+                let source_chars = source.chars().collect::<Vec<_>>();
+                let (short, long) = match query_chars.len() < source_chars.len() {
+                    true => (query_chars.len(), source_chars.len()),
+                    false => (source_chars.len(), query_chars.len()),
+                };
+                // the length difference alone already exceeds the allowed edits
+                if long > 0 && (short as f64 / long as f64) < threshold {
+                    return None;
+                }
+                let score = similarity(&query_chars, &source_chars);
+                (score >= threshold).then(|| TmMatch {
+                    source: source.clone(),
+                    target: target.clone(),
+                    score,
+                })
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(limit);
+        matches
+    }
+
+    /// Exact match or the best fuzzy match with a similarity of at least `threshold`
+    pub fn best(
+        &self,
+        from: Language,
+        to: Language,
+        query: &str,
+        threshold: f64,
+    ) -> Option<TmMatch> {
+        if let Some(target) = self.exact(from, to, query) {
+            return Some(TmMatch {
+                source: query.to_owned(),
+                target: target.to_owned(),
+                score: 1.0,
+            });
+        }
+        if threshold >= 1.0 {
+            return None;
+        }
+        self.fuzzy(from, to, query, threshold, 1).pop()
+    }
+
+    pub fn from_tmx(xml: &str) -> anyhow::Result<Self> {
+        let mut memory = Self::new();
+        memory.import_tmx(xml)?;
+        Ok(memory)
+    }
+
+    pub fn load_tmx<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        Self::from_tmx(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save_tmx<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        std::fs::write(path, self.to_tmx()?)?;
+        Ok(())
+    }
+
+    /// Imports all translation units of a TMX 1.4 document. Returns the number of added pairs.
+    ///
+    /// If the header names a `srclang` only pairs from that language are added, otherwise every
+    /// variant of a unit is paired with every other variant.
+    pub fn import_tmx(&mut self, xml: &str) -> anyhow::Result<usize> {
+        let mut reader = Reader::from_str(xml);
+        let mut srclang = None;
+        let mut variants: Vec<(Option<Language>, String)> = vec![];
+        let mut lang = None;
+        let mut seg: Option<String> = None;
+        // depth of inline native code (`<bpt>`, `<ph>`, ...) whose content is not text
+        let mut skip = 0usize;
+        let mut added = 0;
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) => match e.name().as_ref() {
+                    b"header" => srclang = tmx_lang(&e, "srclang")?,
+                    b"tu" => variants.clear(),
+                    b"tuv" => lang = tmx_lang(&e, "xml:lang")?.or(tmx_lang(&e, "lang")?),
+                    b"seg" => seg = Some(String::new()),
+                    b"bpt" | b"ept" | b"ph" | b"it" | b"ut" if seg.is_some() => skip += 1,
+                    _ => {}
+                },
+                Event::Empty(e) if e.name().as_ref() == b"header" => {
+                    srclang = tmx_lang(&e, "srclang")?;
+                }
+                Event::Text(e) => {
+                    if let Some(seg) = seg.as_mut().filter(|_| skip == 0) {
+                        seg.push_str(&e.decode()?);
+                    }
+                }
+                Event::CData(e) => {
+                    if let Some(seg) = seg.as_mut().filter(|_| skip == 0) {
+                        seg.push_str(&e.decode()?);
+                    }
+                }
+                Event::GeneralRef(e) => {
+                    if let Some(seg) = seg.as_mut().filter(|_| skip == 0) {
+                        match e.resolve_char_ref()? {
+                            Some(ch) => seg.push(ch),
+                            None => {
+                                let name = e.decode()?;
+                                match resolve_predefined_entity(&name) {
+                                    Some(v) => seg.push_str(v),
+                                    None => bail!("unknown entity &{name};"),
+                                }
+                            }
+                        }
+                    }
+                }
+                Event::End(e) => match e.name().as_ref() {
+                    b"seg" => {
+                        if let Some(seg) = seg.take() {
+                            variants.push((lang, seg));
+                        }
+                    }
+                    b"tuv" => lang = None,
+                    b"bpt" | b"ept" | b"ph" | b"it" | b"ut" if seg.is_some() => {
+                        skip = skip.saturating_sub(1)
+                    }
+                    b"tu" => {
+                        let has_src = variants.iter().any(|v| v.0.is_some() && v.0 == srclang);
+                        for (from, source) in &variants {
+                            let Some(from) = from else { continue };
+                            if has_src && Some(*from) != srclang {
+                                continue;
+                            }
+                            for (to, target) in &variants {
+                                match to {
+                                    Some(to) if to != from => {
+                                        self.insert(*from, *to, source.clone(), target.clone());
+                                        added += 1;
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                        variants.clear();
+                    }
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(added)
+    }
+
+    /// Exports the memory as TMX 1.4. Languages without a language tag are skipped.
+    pub fn to_tmx(&self) -> anyhow::Result<String> {
+        let mut pairs = self
+            .entries
+            .iter()
+            .filter_map(|((from, to), entries)| Some((from.to_tag()?, to.to_tag()?, entries)))
+            .collect::<Vec<_>>();
+        pairs.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+
+        let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+        writer
+            .create_element("tmx")
+            .with_attribute(("version", "1.4"))
+            .write_inner_content(|w| {
+                w.create_element("header")
+                    .with_attributes([
+                        ("creationtool", "aio-translator"),
+                        ("creationtoolversion", env!("CARGO_PKG_VERSION")),
+                        ("segtype", "sentence"),
+                        ("o-tmf", "aio-translator"),
+                        ("adminlang", "en"),
+                        ("srclang", "*all*"),
+                        ("datatype", "plaintext"),
+                    ])
+                    .write_empty()?;
+                w.create_element("body").write_inner_content(|w| {
+                    for (from, to, entries) in &pairs {
+                        for (source, target) in entries.iter() {
+                            w.create_element("tu").write_inner_content(|w| {
+                                for (lang, text) in [(from, source), (to, target)] {
+                                    w.create_element("tuv")
+                                        .with_attribute(("xml:lang", *lang))
+                                        .write_inner_content(|w| {
+                                            w.create_element("seg")
+                                                .write_text_content(BytesText::new(text))?;
+                                            Ok(())
+                                        })?;
+                                }
+                                Ok(())
+                            })?;
+                        }
+                    }
+                    Ok(())
+                })?;
+                Ok(())
+            })?;
+        Ok(String::from_utf8(writer.into_inner())?)
+    }
+}
+
+fn tmx_lang(e: &BytesStart, attr: &str) -> anyhow::Result<Option<Language>> {
+    Ok(match e.try_get_attribute(attr)? {
+        Some(v) => Language::from_tag(&v.unescape_value()?),
+        None => None,
+    })
+}
+
+/// Normalized edit distance similarity. 1.0 means equal
+fn similarity(a: &[char], b: &[char]) -> f64 {
+    let len = a.len().max(b.len());
+    if len == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / len as f64
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+/// Answers queries from a [`TranslationMemory`] before calling the wrapped translator
+pub struct MemoryLookup<T: AsyncTranslator> {
+    t: T,
+    memory: TranslationMemory,
+    /// Min similarity for a hit to be returned instead of translated
+    accept_threshold: f64,
+    /// Min similarity and max count of hits passed to the prompt as examples
+    context: Option<(f64, usize)>,
+}
+
+impl<T: AsyncTranslator> MemoryLookup<T> {
+    /// Create a new MemoryLookup wrapper
+    /// - `accept_threshold`: hits with at least this similarity are returned as is. 1.0 only accepts exact matches
+    /// - `context`: Some((threshold, n)) adds up to n hits with at least `threshold` similarity
+    ///   as few-shot examples to the [`PromptBuilder`]
+    pub fn new(
+        t: T,
+        memory: TranslationMemory,
+        accept_threshold: f64,
+        context: Option<(f64, usize)>,
+    ) -> Self {
+        Self {
+            t,
+            memory,
+            accept_threshold,
+            context,
+        }
+    }
+
+    pub fn memory(&self) -> &TranslationMemory {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut TranslationMemory {
+        &mut self.memory
+    }
+
+    fn add_examples(
+        &self,
+        context: Option<PromptBuilder>,
+        from: Language,
+        to: Language,
+        queries: &[String],
+    ) -> Option<PromptBuilder> {
+        let mut context = context?;
+        let Some((threshold, limit)) = self.context else {
+            return Some(context);
+        };
+        let mut matches = queries
+            .iter()
+            .flat_map(|q| self.memory.fuzzy(from, to, q, threshold, limit))
+            .collect::<Vec<_>>();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.dedup_by(|a, b| a.source == b.source);
+        for m in matches.into_iter().take(limit) {
+            context.add_example(m.source, m.target);
+        }
+        Some(context)
+    }
+}
+
+#[async_trait]
+impl<T: AsyncTranslator + Send + Sync> AsyncTranslator for MemoryLookup<T> {
+    fn local(&self) -> bool {
+        self.t.local()
+    }
+
+    fn limits(&self) -> Limits {
+        self.t.limits()
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        self.t.supports(from, to)
+    }
+
+    async fn translate(
+        &self,
+        query: &str,
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationOutput> {
+        let Some(from) = from else {
+            return self.t.translate(query, context, from, to).await;
+        };
+        if let Some(hit) = self.memory.best(from, *to, query, self.accept_threshold) {
+            return Ok(TranslationOutput {
+                text: hit.target,
+                lang: Some(from),
+            });
+        }
+        let context = self.add_examples(context, from, *to, &[query.to_owned()]);
+        self.t.translate(query, context, Some(from), to).await
+    }
+
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        context: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        let Some(from) = from else {
+            return self.t.translate_vec(query, context, from, to).await;
+        };
+        let hits = query
+            .iter()
+            .map(|q| {
+                self.memory
+                    .best(from, *to, q, self.accept_threshold)
+                    .map(|v| v.target)
+            })
+            .collect::<Vec<_>>();
+        let missing = query
+            .iter()
+            .zip(&hits)
+            .filter(|(_, hit)| hit.is_none())
+            .map(|(q, _)| q.clone())
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(TranslationListOutput {
+                text: hits.into_iter().flatten().collect(),
+                lang: Some(from),
+            });
+        }
+
+        let context = self.add_examples(context, from, *to, &missing);
+        let trans = self
+            .t
+            .translate_vec(&missing, context, Some(from), to)
+            .await?;
+        let mut translated = trans.text.into_iter();
+        let text = hits
+            .into_iter()
+            .map(|hit| match hit {
+                Some(hit) => Ok(hit),
+                None => translated.next().ok_or(Error::NoResponse),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(TranslationListOutput {
+            text,
+            lang: trans.lang.or(Some(from)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tmx version="1.4">
+  <header creationtool="test" creationtoolversion="1" segtype="sentence" o-tmf="test" adminlang="en" srclang="en" datatype="plaintext"/>
+  <body>
+    <tu>
+      <tuv xml:lang="en-US"><seg>Hello &amp; welcome</seg></tuv>
+      <tuv xml:lang="de-DE"><seg>Hallo und willkommen</seg></tuv>
+    </tu>
+    <tu>
+      <tuv xml:lang="en"><seg>Press <ph>&lt;b&gt;</ph>start</seg></tuv>
+      <tuv xml:lang="de"><seg>Drücke start</seg></tuv>
+    </tu>
+  </body>
+</tmx>"#;
+
+    struct Echo;
+
+    #[async_trait]
+    impl AsyncTranslator for Echo {
+        fn local(&self) -> bool {
+            true
+        }
+
+        async fn translate(
+            &self,
+            query: &str,
+            _: Option<PromptBuilder>,
+            from: Option<Language>,
+            _: &Language,
+        ) -> anyhow::Result<TranslationOutput> {
+            Ok(TranslationOutput {
+                text: format!("echo {query}"),
+                lang: from,
+            })
+        }
+
+        async fn translate_vec(
+            &self,
+            query: &[String],
+            _: Option<PromptBuilder>,
+            from: Option<Language>,
+            _: &Language,
+        ) -> anyhow::Result<TranslationListOutput> {
+            Ok(TranslationListOutput {
+                text: query.iter().map(|q| format!("echo {q}")).collect(),
+                lang: from,
+            })
+        }
+    }
+
+    #[test]
+    fn tmx_roundtrip() {
+        let memory = TranslationMemory::from_tmx(TMX).unwrap();
+        assert_eq!(memory.len(), 2);
+        assert_eq!(
+            memory.exact(Language::English, Language::German, "Hello & welcome"),
+            Some("Hallo und willkommen")
+        );
+        assert_eq!(
+            memory.exact(Language::English, Language::German, "Press start"),
+            Some("Drücke start")
+        );
+        assert_eq!(
+            memory.exact(Language::German, Language::English, "Drücke start"),
+            None
+        );
+
+        let memory2 = TranslationMemory::from_tmx(&memory.to_tmx().unwrap()).unwrap();
+        assert_eq!(memory2.len(), 4);
+        assert_eq!(
+            memory2.exact(Language::English, Language::German, "Hello & welcome"),
+            Some("Hallo und willkommen")
+        );
+    }
+
+    #[test]
+    fn fuzzy_match() {
+        let mut memory = TranslationMemory::new();
+        memory.insert(
+            Language::English,
+            Language::German,
+            "Open the door",
+            "Öffne die Tür",
+        );
+        memory.insert(
+            Language::English,
+            Language::German,
+            "Close the window",
+            "Schließe das Fenster",
+        );
+
+        let hit = memory
+            .best(Language::English, Language::German, "Open the doors", 0.8)
+            .unwrap();
+        assert_eq!(hit.target, "Öffne die Tür");
+        assert!(hit.score < 1.0);
+        assert!(
+            memory
+                .best(Language::English, Language::German, "Open the doors", 1.0)
+                .is_none()
+        );
+        assert!(
+            memory
+                .best(Language::English, Language::French, "Open the door", 0.5)
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn lookup_before_translate() {
+        let mut memory = TranslationMemory::new();
+        memory.insert(Language::English, Language::German, "Hello", "Hallo");
+        let t = MemoryLookup::new(Echo, memory, 1.0, None);
+        let out = t
+            .translate_vec(
+                &["Hello".to_owned(), "World".to_owned()],
+                None,
+                Some(Language::English),
+                &Language::German,
+            )
+            .await
+            .unwrap();
+        assert_eq!(out.text, vec!["Hallo".to_owned(), "echo World".to_owned()]);
+    }
+}
@@ -0,0 +1,157 @@
+use aio_translator_interface::{
+    AsyncDetector, Language,
+    error::{Error, check_status},
+};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::BaiduApiError;
+
+/// Detector using the language detection endpoint of the Baidu translation api
+pub struct BaiduDetector {
+    url: String,
+    app_id: String,
+    key: String,
+    client: Client,
+}
+
+impl BaiduDetector {
+    pub fn new(app_id: &str, key: &str) -> Self {
+        Self {
+            url: "https://fanyi-api.baidu.com/api/trans/vip/language".to_string(),
+            app_id: app_id.to_string(),
+            key: key.to_string(),
+            client: Client::new(),
+        }
+    }
+
+    /// Send requests to `url` instead, e.g. a proxy
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+}
+
+#[async_trait]
+impl AsyncDetector for BaiduDetector {
+    async fn detect(&self, text: &str) -> anyhow::Result<Option<Language>> {
+        let form = Form::new(&self.app_id, text, "0", &self.key);
+        let resp: Response = self
+            .client
+            .post(&self.url)
+            .form(&form)
+            .send()
+            .await
+            .map_err(Error::from)
+            .and_then(check_status)?
+            .json()
+            .await?;
+        // the detection endpoint sends the error code as a number, 0 on success
+        let code = match resp.error_code {
+            Value::String(v) => v,
+            v => v.to_string(),
+        };
+        if code != "0" && code != "52000" {
+            Err(BaiduApiError {
+                code,
+                msg: resp.error_msg,
+                data: None,
+            }
+            .into_error())?;
+        }
+        let src = resp.data.ok_or(Error::NoResponse)?.src;
+        Ok(Some(
+            Language::from_baidu(&src).ok_or(Error::CouldNotMapLanguage(Some(src)))?,
+        ))
+    }
+}
+
+/// The data submitted by the form
+#[derive(Debug, Serialize)]
+struct Form {
+    q: String,
+    appid: String,
+    salt: String,
+    sign: String,
+}
+
+impl Form {
+    fn new(appid: &str, q: &str, salt: &str, key: &str) -> Self {
+        let data = format!("{}{}{}{}", &appid, q, salt, key);
+        let sign = format!("{:x}", md5::compute(data));
+        Self {
+            q: q.to_string(),
+            appid: appid.to_string(),
+            salt: salt.to_string(),
+            sign,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Detected {
+    src: String,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    error_code: Value,
+    #[serde(default)]
+    error_msg: String,
+    data: Option<Detected>,
+}
+
+#[cfg(test)]
+mod tests {
+    use aio_translator_interface::{AsyncDetector as _, Language, error::Error};
+    use serde_json::json;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_string_contains, method},
+    };
+
+    use crate::BaiduDetector;
+
+    #[tokio::test]
+    async fn detect() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("appid=app"))
+            .and(body_string_contains("q=Bonjour"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "error_code": 0,
+                "error_msg": "success",
+                "data": { "src": "fra" }
+            })))
+            .mount(&server)
+            .await;
+        let d = BaiduDetector::new("app", "key").with_url(server.uri());
+
+        assert_eq!(d.detect("Bonjour").await.unwrap(), Some(Language::French));
+        assert_eq!(
+            d.rank("Bonjour").await.unwrap(),
+            vec![(Language::French, 1.0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_sign() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "error_code": 54001,
+                "error_msg": "Invalid Sign"
+            })))
+            .mount(&server)
+            .await;
+        let d = BaiduDetector::new("app", "key").with_url(server.uri());
+
+        let err = d.detect("Bonjour").await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::Unauthorized)
+        ));
+    }
+}
@@ -2,7 +2,9 @@
 
 use aio_translator_interface::{
     AsyncTranslator, Language, TranslationListOutput, TranslationOutput,
-    error::{ApiError, Error},
+    batch::Batcher,
+    chunk::{LengthUnit, Limits},
+    error::{ApiError, Error, check_status},
     prompt::PromptBuilder,
 };
 use async_trait::async_trait;
@@ -10,6 +12,10 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+mod detector;
+
+pub use detector::BaiduDetector;
+
 pub struct BaiduTranslator {
     url: String,
     app_id: String,
@@ -22,6 +28,20 @@ impl AsyncTranslator for BaiduTranslator {
     fn local(&self) -> bool {
         false
     }
+
+    fn limits(&self) -> Limits {
+        Limits {
+            unit: LengthUnit::Bytes,
+            max_request_len: Some(6000),
+            overhead: 1,
+            ..Default::default()
+        }
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        from.is_none_or(|v| v.to_baidu().is_some()) && to.to_baidu().is_some()
+    }
+
     async fn translate(
         &self,
         query: &str,
@@ -40,18 +60,14 @@ impl AsyncTranslator for BaiduTranslator {
             .post(&self.url)
             .form(&form)
             .send()
-            .await?
+            .await
+            .map_err(Error::from)
+            .and_then(check_status)?
             .json()
             .await?;
         let resp = match resp {
             Response::Ok(v) => v,
-            Response::Err(v) => {
-                Err(Error::ApiError(ApiError::Baidu {
-                    message: v.solution().to_owned(),
-                    code: v.code,
-                }))?;
-                unreachable!()
-            }
+            Response::Err(v) => Err(v.into_error())?,
         };
         Ok(TranslationOutput {
             text: resp
@@ -73,11 +89,11 @@ impl AsyncTranslator for BaiduTranslator {
         from: Option<Language>,
         to: &Language,
     ) -> anyhow::Result<TranslationListOutput> {
-        let v = self.translate(&query.join("\n"), None, from, to).await?;
-        Ok(TranslationListOutput {
-            text: v.text.split('\n').map(|v| v.to_string()).collect(),
-            lang: v.lang,
-        })
+        Batcher::newline()
+            .translate(query, |q| async move {
+                self.translate(&q, None, from, to).await
+            })
+            .await
     }
 }
 
@@ -150,6 +166,21 @@ impl std::fmt::Display for BaiduApiError {
 }
 
 impl BaiduApiError {
+    fn into_error(self) -> Error {
+        match self.code.as_str() {
+            // access frequency limited
+            "54003" => Error::RateLimited(None),
+            // unauthorized user, invalid sign
+            "52003" | "54001" => Error::Unauthorized,
+            // insufficient balance
+            "54004" => Error::QuotaExceeded,
+            _ => Error::ApiError(ApiError::Baidu {
+                message: self.solution().to_owned(),
+                code: self.code,
+            }),
+        }
+    }
+
     ///Reference: [Error Code List](https://fanyi-api.baidu.com/doc/21)
     pub fn solution(&self) -> &str {
         match self.code.as_bytes() {
@@ -1,5 +1,6 @@
 use aio_translator_interface::{
-    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, error::Error,
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput,
+    error::{Error, check_status},
     prompt::PromptBuilder,
 };
 
@@ -31,6 +32,11 @@ impl AsyncTranslator for CaiyunTranslator {
     fn local(&self) -> bool {
         false
     }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        from.is_none_or(|v| v.to_caiyun().is_some()) && to.to_caiyun().is_some()
+    }
+
     async fn translate(
         &self,
         query: &str,
@@ -78,7 +84,9 @@ impl AsyncTranslator for CaiyunTranslator {
             .header("x-authorization", format!("token {}", self.token))
             .json(&request)
             .send()
-            .await?
+            .await
+            .map_err(Error::from)
+            .and_then(check_status)?
             .json()
             .await?;
         Ok(TranslationListOutput {
@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 
 use aio_translator_interface::{
-    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, error::Error,
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput,
+    chunk::{LengthUnit, Limits},
+    error::{Error, check_status},
     prompt::PromptBuilder,
 };
 
@@ -47,6 +49,20 @@ impl AsyncTranslator for DeeplTranslator {
     fn local(&self) -> bool {
         false
     }
+
+    fn limits(&self) -> Limits {
+        Limits {
+            unit: LengthUnit::Bytes,
+            max_request_len: Some(128 * 1024),
+            max_segments: Some(50),
+            ..Default::default()
+        }
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        from.is_none_or(|v| v.to_deepl().is_some()) && to.to_deepl().is_some()
+    }
+
     async fn translate(
         &self,
         query: &str,
@@ -86,7 +102,9 @@ impl AsyncTranslator for DeeplTranslator {
             .header("Authorization", format!("DeepL-Auth-Key {}", self.auth))
             .json(&body)
             .send()
-            .await?
+            .await
+            .map_err(Error::from)
+            .and_then(check_status)?
             .json()
             .await?;
         let (texts, langs): (Vec<String>, Vec<String>) = request
@@ -0,0 +1,125 @@
+use aio_translator_interface::{
+    AsyncDetector, Language,
+    detect::rank_scores,
+    error::{Error, check_status},
+};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+
+/// Detector using the `/detect` endpoint of the Google translation api
+pub struct GoogleDetector {
+    client: Client,
+    api_key: String,
+    url: String,
+}
+
+impl GoogleDetector {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: Client::new(),
+            api_key,
+            url: "https://translation.googleapis.com/language/translate/v2/detect".to_owned(),
+        }
+    }
+
+    /// Send requests to `url` instead, e.g. a proxy
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncDetector for GoogleDetector {
+    async fn detect(&self, text: &str) -> anyhow::Result<Option<Language>> {
+        Ok(self.rank(text).await?.first().map(|v| v.0))
+    }
+
+    async fn rank(&self, text: &str) -> anyhow::Result<Vec<(Language, f64)>> {
+        let resp: Root = self
+            .client
+            .post(format!("{}?key={}", self.url, self.api_key))
+            .json(&json!({ "q": text }))
+            .send()
+            .await
+            .map_err(Error::from)
+            .and_then(check_status)?
+            .json()
+            .await?;
+        Ok(rank_scores(
+            resp.data
+                .detections
+                .into_iter()
+                .flatten()
+                // `und` is reported for text without a language
+                .filter_map(|v| Some((Language::from_google(&v.language)?, v.confidence))),
+        ))
+    }
+}
+
+#[derive(Deserialize)]
+struct Detection {
+    language: String,
+    #[serde(default)]
+    confidence: f64,
+}
+#[derive(Deserialize)]
+struct Data {
+    detections: Vec<Vec<Detection>>,
+}
+#[derive(Deserialize)]
+struct Root {
+    data: Data,
+}
+
+#[cfg(test)]
+mod tests {
+    use aio_translator_interface::{AsyncDetector as _, Language};
+    use serde_json::json;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_json, method, path, query_param},
+    };
+
+    use crate::GoogleDetector;
+
+    #[tokio::test]
+    async fn detect() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/detect"))
+            .and(query_param("key", "key"))
+            .and(body_json(json!({ "q": "Hallo Welt" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "detections": [[
+                    { "language": "de", "isReliable": false, "confidence": 0.8 },
+                    { "language": "nl", "isReliable": false, "confidence": 0.2 }
+                ]] }
+            })))
+            .mount(&server)
+            .await;
+        let d = GoogleDetector::new("key".to_owned()).with_url(format!("{}/detect", server.uri()));
+
+        assert_eq!(
+            d.detect("Hallo Welt").await.unwrap(),
+            Some(Language::German)
+        );
+        assert_eq!(
+            d.rank("Hallo Welt").await.unwrap(),
+            vec![(Language::German, 0.8), (Language::Dutch, 0.2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn unauthorized() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+        let d = GoogleDetector::new("key".to_owned()).with_url(server.uri());
+
+        assert!(d.detect("Hallo Welt").await.is_err());
+    }
+}
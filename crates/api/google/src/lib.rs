@@ -1,11 +1,17 @@
 use aio_translator_interface::{
-    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, error::Error,
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput,
+    chunk::Limits,
+    error::{Error, check_status},
     prompt::PromptBuilder,
 };
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+mod detector;
+
+pub use detector::GoogleDetector;
+
 pub struct GoogleTranslator {
     client: Client,
     api_key: String,
@@ -29,6 +35,19 @@ impl AsyncTranslator for GoogleTranslator {
     fn local(&self) -> bool {
         false
     }
+
+    fn limits(&self) -> Limits {
+        Limits {
+            max_request_len: Some(30_000),
+            max_segments: Some(128),
+            ..Default::default()
+        }
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        from.is_none_or(|v| v.to_google().is_some()) && to.to_google().is_some()
+    }
+
     async fn translate(
         &self,
         query: &str,
@@ -65,7 +84,9 @@ impl AsyncTranslator for GoogleTranslator {
                 None => json!({"q": query, "target": to.to_google().ok_or(Error::UnknownLanguage(*to))?, "format": "text"}),
             })
             .send()
-            .await?
+            .await
+            .map_err(Error::from)
+            .and_then(check_status)?
             .json()
             .await?;
         Ok(TranslationListOutput {
@@ -1,5 +1,8 @@
 use aio_translator_interface::{
-    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, error::Error,
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput,
+    batch::Batcher,
+    chunk::{LengthUnit, Limits},
+    error::{Error, check_status},
     prompt::PromptBuilder,
 };
 
@@ -34,6 +37,20 @@ impl AsyncTranslator for MyMemoryTranslator {
     fn local(&self) -> bool {
         false
     }
+
+    fn limits(&self) -> Limits {
+        Limits {
+            unit: LengthUnit::Bytes,
+            max_request_len: Some(self.input_limit as usize),
+            overhead: "_._._".len(),
+            ..Default::default()
+        }
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        from.is_none_or(|v| v.to_mymemory().is_some()) && to.to_mymemory().is_some()
+    }
+
     async fn translate(
         &self,
         query: &str,
@@ -61,10 +78,7 @@ impl AsyncTranslator for MyMemoryTranslator {
             .header(REFERER, "https://mymemory.translated.net")
             .send()
             .await?;
-        if !response.status().is_success() {
-            Err(Error::RequestFailed(response.status().as_u16()))?;
-            unreachable!()
-        }
+        let response = check_status(response)?;
         let resp: Value = response.json().await?;
         let resp = &resp["responseData"];
         let lang = resp["detectedLanguage"].to_string();
@@ -93,15 +107,11 @@ impl AsyncTranslator for MyMemoryTranslator {
         from: Option<Language>,
         to: &Language,
     ) -> anyhow::Result<TranslationListOutput> {
-        let t = self.translate(&query.join("_._._"), None, from, to).await?;
-        Ok(TranslationListOutput {
-            text: t
-                .text
-                .split("_._._")
-                .map(|s| s.to_string())
-                .collect::<Vec<_>>(),
-            lang: t.lang,
-        })
+        Batcher::delimiter("_._._")
+            .translate(query, |q| async move {
+                self.translate(&q, None, from, to).await
+            })
+            .await
     }
 }
 
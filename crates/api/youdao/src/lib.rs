@@ -1,7 +1,10 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use aio_translator_interface::{
-    AsyncTranslator, Language, TranslationListOutput, TranslationOutput, error::Error,
+    AsyncTranslator, Language, TranslationListOutput, TranslationOutput,
+    batch::Batcher,
+    chunk::Limits,
+    error::{Error, check_status},
     prompt::PromptBuilder,
 };
 use rand::Rng as _;
@@ -54,6 +57,19 @@ impl AsyncTranslator for YoudaoTranslator {
     fn local(&self) -> bool {
         false
     }
+
+    fn limits(&self) -> Limits {
+        Limits {
+            max_request_len: Some(5000),
+            overhead: 1,
+            ..Default::default()
+        }
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        from.is_none_or(|v| v.to_youdao().is_some()) && to.to_youdao().is_some()
+    }
+
     async fn translate(
         &self,
         query: &str,
@@ -61,32 +77,15 @@ impl AsyncTranslator for YoudaoTranslator {
         from: Option<Language>,
         to: &Language,
     ) -> anyhow::Result<TranslationOutput> {
-        let mut t = self
-            .translate_vec(&vec![query.to_owned()], None, from, to)
-            .await?;
-        Ok(TranslationOutput {
-            text: t.text.remove(0),
-            lang: None,
-        })
-    }
-
-    async fn translate_vec(
-        &self,
-        query: &[String],
-        _: Option<PromptBuilder>,
-        from: Option<Language>,
-        to: &Language,
-    ) -> anyhow::Result<TranslationListOutput> {
         let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
         let curtime = now.as_secs();
         let nanos = now.subsec_nanos();
         let ts = Timestamp::from_unix(&self.context, curtime, nanos);
         let salt = Uuid::new_v1(ts, &self.mac).to_string();
-        let query = query.join("\n");
         let sign_str = format!(
             "{}{}{}{}{}",
             self.app_key,
-            truncate(&query),
+            truncate(query),
             salt,
             curtime,
             self.app_secret
@@ -105,23 +104,35 @@ impl AsyncTranslator for YoudaoTranslator {
                 ("signType", "v3"),
                 ("curtime", &curtime.to_string()),
                 ("appKey", self.app_key.as_str()),
-                ("q", query.as_str()),
+                ("q", query),
                 ("salt", salt.as_str()),
                 ("sign", &sha256_encode(&sign_str)),
             ])
             .send()
-            .await?
+            .await
+            .map_err(Error::from)
+            .and_then(check_status)?
             .json()
             .await?;
-        Ok(TranslationListOutput {
-            text: data
-                .translation
-                .into_iter()
-                .flat_map(|v| v.split("/n").map(|v| v.to_owned()).collect::<Vec<String>>())
-                .collect::<Vec<String>>(),
+        Ok(TranslationOutput {
+            text: data.translation.join("\n"),
             lang: None,
         })
     }
+
+    async fn translate_vec(
+        &self,
+        query: &[String],
+        _: Option<PromptBuilder>,
+        from: Option<Language>,
+        to: &Language,
+    ) -> anyhow::Result<TranslationListOutput> {
+        Batcher::newline()
+            .translate(query, |q| async move {
+                self.translate(&q, None, from, to).await
+            })
+            .await
+    }
 }
 
 #[derive(Deserialize)]
@@ -0,0 +1,109 @@
+use aio_translator_interface::{Detector, Language, Model, detect::rank_scores};
+use fasttext_rs::FastText;
+use interface_model::{
+    ModelLoad, ModelRead, ModelSource, ModelWrap, impl_model_helpers, impl_model_load_helpers,
+};
+use maplit::hashmap;
+
+/// Candidates asked from the model for [`Detector::detect_ranked`]
+const TOP_K: i32 = 5;
+
+/// Detector using fastText's lid.176 model, robust on noisy text.
+/// The model has to be loaded with [`ModelLoad::load`] before detecting, until then nothing is detected.
+pub struct FastTextDetector {
+    loaded_models: ModelWrap<FastText>,
+}
+
+impl FastTextDetector {
+    pub fn new() -> Self {
+        Self {
+            loaded_models: Default::default(),
+        }
+    }
+
+    fn predict(&self, s: &str, k: i32) -> Vec<(Language, f64)> {
+        let Ok(model) = self.loaded_models.try_read() else {
+            return vec![];
+        };
+        let Some(model) = model.as_ref() else {
+            return vec![];
+        };
+        // fastText reads a line at a time
+        let s = s.replace(['\n', '\r'], " ");
+        let predictions = model.predict(&s, k, 0.0).unwrap_or_default();
+        rank_scores(predictions.into_iter().filter_map(|v| {
+            let lang = to_language(&v.label)?;
+            Some((lang, v.prob as f64))
+        }))
+    }
+}
+
+/// Maps labels like `__label__en` or `__label__als` through the iso 639 columns
+fn to_language(label: &str) -> Option<Language> {
+    Language::from_tag(label.strip_prefix("__label__").unwrap_or(label))
+}
+
+impl Detector for FastTextDetector {
+    fn detect_language(&self, s: &str) -> Option<Language> {
+        self.predict(s, 1).first().map(|v| v.0)
+    }
+
+    fn detect_ranked(&self, s: &str) -> Vec<(Language, f64)> {
+        self.predict(s, TOP_K)
+    }
+}
+
+#[async_trait::async_trait]
+impl ModelLoad for FastTextDetector {
+    impl_model_load_helpers!(loaded_models, FastText);
+
+    async fn reload(&self) -> anyhow::Result<ModelRead<'_, Self::T>> {
+        let path = self.download_model("lid.176", "lid.176.bin").await?;
+        let mut model = FastText::new();
+        model
+            .load_model(&path.to_string_lossy())
+            .map_err(anyhow::Error::msg)?;
+        *self.loaded_models.write().await = Some(model);
+        Ok(self.get_model().await.unwrap())
+    }
+}
+
+impl Model for FastTextDetector {
+    impl_model_helpers!("detector", "fasttext", loaded_models);
+
+    fn models(&self) -> std::collections::HashMap<&'static str, interface_model::ModelSource> {
+        hashmap! {
+            "lid.176" => ModelSource {
+                url: "https://dl.fbaipublicfiles.com/fasttext/supervised-models/lid.176.bin",
+                hash: "7e69ec5451bc261cc7844e49e4792a85d7f09c06789ec800fc4a44aec362764e",
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels() {
+        assert_eq!(to_language("__label__en"), Some(Language::English));
+        assert_eq!(to_language("__label__ja"), Some(Language::Japanese));
+        assert_eq!(to_language("__label__ceb"), Some(Language::Cebuano));
+    }
+
+    #[tokio::test]
+    async fn test_detect() {
+        let d = FastTextDetector::new();
+        assert_eq!(d.detect_language("Hello world"), None);
+        assert!(d.load().await.is_ok());
+        assert_eq!(
+            d.detect_language("Das ist ein kurzer deutscher Satz."),
+            Some(Language::German)
+        );
+        assert_eq!(
+            d.detect_ranked("これは日本語の文です。")[0].0,
+            Language::Japanese
+        );
+    }
+}
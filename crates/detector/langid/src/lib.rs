@@ -1,6 +1,20 @@
 use std::sync::Arc;
 
-use aio_translator_interface::{Detector, Language};
+use aio_translator_interface::{
+    Detector, Language,
+    detect::{rank_scores, split_allowlist},
+};
+
+/// ISO 639-1 codes of the languages the bundled langid model knows
+const LANGUAGES: [&str; 97] = [
+    "af", "am", "an", "ar", "as", "az", "be", "bg", "bn", "br", "bs", "ca", "cs", "cy", "da", "de",
+    "dz", "el", "en", "eo", "es", "et", "eu", "fa", "fi", "fo", "fr", "ga", "gl", "gu", "he", "hi",
+    "hr", "ht", "hu", "hy", "id", "is", "it", "ja", "jv", "ka", "kk", "km", "kn", "ko", "ku", "ky",
+    "la", "lb", "lo", "lt", "lv", "mg", "mk", "ml", "mn", "mr", "ms", "mt", "nb", "ne", "nl", "nn",
+    "no", "oc", "or", "pa", "pl", "ps", "pt", "qu", "ro", "ru", "rw", "se", "si", "sk", "sl", "sq",
+    "sr", "sv", "sw", "ta", "te", "th", "tl", "tr", "ug", "uk", "ur", "vi", "vo", "wa", "xh", "zh",
+    "zu",
+];
 
 #[derive(Clone)]
 pub struct LangIdDetector {
@@ -13,6 +27,19 @@ impl LangIdDetector {
             m: Arc::new(langid_rs::Model::load(false)?),
         })
     }
+
+    /// Create a LangIdDetector that only chooses between `allowlist`.
+    /// Returns the languages langid doesn't support alongside.
+    pub fn with_languages(allowlist: &[Language]) -> std::io::Result<(Self, Vec<Language>)> {
+        let (languages, unsupported) = split_allowlist(allowlist, |v| {
+            v.to_639_1()
+                .filter(|code| LANGUAGES.contains(code))
+                .map(ToString::to_string)
+        });
+        let mut m = langid_rs::Model::load(false)?;
+        m.set_langs(Some(languages));
+        Ok((Self { m: Arc::new(m) }, unsupported))
+    }
 }
 
 impl Detector for LangIdDetector {
@@ -20,4 +47,45 @@ impl Detector for LangIdDetector {
         let (lang, _) = self.m.classify(s)?;
         Language::from_639_1(lang)
     }
+
+    /// langid only reports the probability of its best guess
+    fn detect_ranked(&self, s: &str) -> Vec<(Language, f64)> {
+        let Some((lang, probability)) = self.m.classify(s) else {
+            return vec![];
+        };
+        let Some(lang) = Language::from_639_1(lang) else {
+            return vec![];
+        };
+        rank_scores([(lang, (probability as f64).clamp(0.0, 1.0))])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_ranked() {
+        let d = LangIdDetector::new().unwrap();
+        let ranked = d.detect_ranked("Die Katze sitzt auf der Matte und schläft den ganzen Tag.");
+        assert_eq!(ranked[0].0, Language::German);
+        assert!(ranked[0].1 > 0.0 && ranked[0].1 <= 1.0);
+    }
+
+    #[test]
+    fn with_languages() {
+        const TEXT: &str = "Die Katze sitzt auf der Matte und schläft den ganzen Tag.";
+        let (d, unsupported) = LangIdDetector::with_languages(&[
+            Language::French,
+            Language::German,
+            Language::Klingon,
+        ])
+        .unwrap();
+        assert_eq!(unsupported, vec![Language::Klingon]);
+        assert_eq!(d.detect_language(TEXT), Some(Language::German));
+
+        let (d, _) =
+            LangIdDetector::with_languages(&[Language::French, Language::English]).unwrap();
+        assert_ne!(d.detect_language(TEXT), Some(Language::German));
+    }
 }
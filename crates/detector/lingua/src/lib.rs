@@ -1,95 +1,196 @@
-use aio_translator_interface::{Detector, Language as InterfaceLanguage};
+use std::ops::Range;
+
+use aio_translator_interface::{
+    Detector, Language as InterfaceLanguage,
+    detect::{rank_scores, split_allowlist},
+};
 use lingua_rs::{Language, LanguageDetector, LanguageDetectorBuilder};
 
+/// lingua needs at least two languages to choose between
+enum Languages {
+    Detector(LanguageDetector),
+    /// The only supported language of the allowlist
+    Single(InterfaceLanguage),
+    /// No language of the allowlist is supported
+    None,
+}
+
 pub struct LinguaDetector {
-    d: LanguageDetector,
+    d: Languages,
 }
 
 impl LinguaDetector {
     pub fn new() -> Self {
         let d = LanguageDetectorBuilder::from_all_languages().build();
-        Self { d }
+        Self {
+            d: Languages::Detector(d),
+        }
+    }
+
+    /// Create a LinguaDetector that only chooses between `allowlist`.
+    /// Returns the languages lingua doesn't support alongside.
+    /// With a single supported language every text is detected as that language, with none nothing is detected.
+    pub fn with_languages(allowlist: &[InterfaceLanguage]) -> (Self, Vec<InterfaceLanguage>) {
+        let all = Language::all();
+        let (languages, unsupported) = split_allowlist(allowlist, |v| {
+            all.iter().find(|l| to_language(**l) == v).copied()
+        });
+        let d = match languages.as_slice() {
+            [] => Languages::None,
+            [lang] => Languages::Single(to_language(*lang)),
+            _ => Languages::Detector(LanguageDetectorBuilder::from_languages(&languages).build()),
+        };
+        (Self { d }, unsupported)
     }
 }
 impl Detector for LinguaDetector {
     fn detect_language(&self, s: &str) -> Option<InterfaceLanguage> {
-        let lang = self.d.detect_language_of(s)?;
-        Some(match lang {
-            Language::Afrikaans => InterfaceLanguage::Afrikaans,
-            Language::Albanian => InterfaceLanguage::Albanian,
-            Language::Arabic => InterfaceLanguage::Arabic,
-            Language::Armenian => InterfaceLanguage::Armenian,
-            Language::Azerbaijani => InterfaceLanguage::Azerbaijani,
-            Language::Basque => InterfaceLanguage::Basque,
-            Language::Belarusian => InterfaceLanguage::Belarusian,
-            Language::Bengali => InterfaceLanguage::Bengali,
-            Language::Bokmal => InterfaceLanguage::NorwegianBokmål,
-            Language::Bosnian => InterfaceLanguage::Bosnian,
-            Language::Bulgarian => InterfaceLanguage::Bulgarian,
-            Language::Catalan => InterfaceLanguage::Catalan,
-            Language::Chinese => InterfaceLanguage::Chinese,
-            Language::Croatian => InterfaceLanguage::Croatian,
-            Language::Czech => InterfaceLanguage::Czech,
-            Language::Danish => InterfaceLanguage::Danish,
-            Language::Dutch => InterfaceLanguage::Dutch,
-            Language::English => InterfaceLanguage::English,
-            Language::Esperanto => InterfaceLanguage::Esperanto,
-            Language::Estonian => InterfaceLanguage::Estonian,
-            Language::Finnish => InterfaceLanguage::Finnish,
-            Language::French => InterfaceLanguage::French,
-            Language::Ganda => InterfaceLanguage::Ganda,
-            Language::Georgian => InterfaceLanguage::Georgian,
-            Language::German => InterfaceLanguage::German,
-            Language::Greek => InterfaceLanguage::Greek,
-            Language::Gujarati => InterfaceLanguage::Gujarati,
-            Language::Hebrew => InterfaceLanguage::Hebrew,
-            Language::Hindi => InterfaceLanguage::Hindi,
-            Language::Hungarian => InterfaceLanguage::Hungarian,
-            Language::Icelandic => InterfaceLanguage::Icelandic,
-            Language::Indonesian => InterfaceLanguage::Indonesian,
-            Language::Irish => InterfaceLanguage::Irish,
-            Language::Italian => InterfaceLanguage::Italian,
-            Language::Japanese => InterfaceLanguage::Japanese,
-            Language::Kazakh => InterfaceLanguage::Kazakh,
-            Language::Korean => InterfaceLanguage::Korean,
-            Language::Latin => InterfaceLanguage::Latin,
-            Language::Latvian => InterfaceLanguage::Latvian,
-            Language::Lithuanian => InterfaceLanguage::Lithuanian,
-            Language::Macedonian => InterfaceLanguage::Macedonian,
-            Language::Malay => InterfaceLanguage::Malay,
-            Language::Maori => InterfaceLanguage::Maori,
-            Language::Marathi => InterfaceLanguage::Marathi,
-            Language::Mongolian => InterfaceLanguage::Mongolian,
-            Language::Nynorsk => InterfaceLanguage::NorwegianNynorsk,
-            Language::Persian => InterfaceLanguage::Persian,
-            Language::Polish => InterfaceLanguage::Polish,
-            Language::Portuguese => InterfaceLanguage::Portuguese,
-            Language::Punjabi => InterfaceLanguage::Punjabi,
-            Language::Romanian => InterfaceLanguage::Romanian,
-            Language::Russian => InterfaceLanguage::Russian,
-            Language::Serbian => InterfaceLanguage::Serbian,
-            Language::Shona => InterfaceLanguage::Shona,
-            Language::Slovak => InterfaceLanguage::Slovak,
-            Language::Slovene => InterfaceLanguage::Slovenian,
-            Language::Somali => InterfaceLanguage::Somali,
-            Language::Sotho => InterfaceLanguage::SouthernSotho,
-            Language::Spanish => InterfaceLanguage::Spanish,
-            Language::Swahili => InterfaceLanguage::Swahili,
-            Language::Swedish => InterfaceLanguage::Swedish,
-            Language::Tagalog => InterfaceLanguage::Tagalog,
-            Language::Tamil => InterfaceLanguage::Tamil,
-            Language::Telugu => InterfaceLanguage::Telugu,
-            Language::Thai => InterfaceLanguage::Thai,
-            Language::Tsonga => InterfaceLanguage::Tsonga,
-            Language::Tswana => InterfaceLanguage::Tswana,
-            Language::Turkish => InterfaceLanguage::Turkish,
-            Language::Ukrainian => InterfaceLanguage::Ukrainian,
-            Language::Urdu => InterfaceLanguage::Urdu,
-            Language::Vietnamese => InterfaceLanguage::Vietnamese,
-            Language::Welsh => InterfaceLanguage::Welsh,
-            Language::Xhosa => InterfaceLanguage::Xhosa,
-            Language::Yoruba => InterfaceLanguage::Yoruba,
-            Language::Zulu => InterfaceLanguage::Zulu,
-        })
+        match &self.d {
+            Languages::Detector(d) => d.detect_language_of(s).map(to_language),
+            Languages::Single(lang) => Some(*lang),
+            Languages::None => None,
+        }
+    }
+
+    fn detect_ranked(&self, s: &str) -> Vec<(InterfaceLanguage, f64)> {
+        match &self.d {
+            Languages::Detector(d) => rank_scores(
+                d.compute_language_confidence_values(s)
+                    .into_iter()
+                    .map(|(lang, confidence)| (to_language(lang), confidence)),
+            ),
+            Languages::Single(lang) => vec![(*lang, 1.0)],
+            Languages::None => vec![],
+        }
+    }
+
+    fn detect_spans(&self, s: &str) -> Vec<(Range<usize>, InterfaceLanguage)> {
+        match &self.d {
+            Languages::Detector(d) => d
+                .detect_multiple_languages_of(s)
+                .into_iter()
+                .map(|v| (v.start_index()..v.end_index(), to_language(v.language())))
+                .collect(),
+            Languages::Single(lang) if !s.is_empty() => vec![(0..s.len(), *lang)],
+            _ => vec![],
+        }
+    }
+}
+
+fn to_language(lang: Language) -> InterfaceLanguage {
+    match lang {
+        Language::Afrikaans => InterfaceLanguage::Afrikaans,
+        Language::Albanian => InterfaceLanguage::Albanian,
+        Language::Arabic => InterfaceLanguage::Arabic,
+        Language::Armenian => InterfaceLanguage::Armenian,
+        Language::Azerbaijani => InterfaceLanguage::Azerbaijani,
+        Language::Basque => InterfaceLanguage::Basque,
+        Language::Belarusian => InterfaceLanguage::Belarusian,
+        Language::Bengali => InterfaceLanguage::Bengali,
+        Language::Bokmal => InterfaceLanguage::NorwegianBokmål,
+        Language::Bosnian => InterfaceLanguage::Bosnian,
+        Language::Bulgarian => InterfaceLanguage::Bulgarian,
+        Language::Catalan => InterfaceLanguage::Catalan,
+        Language::Chinese => InterfaceLanguage::Chinese,
+        Language::Croatian => InterfaceLanguage::Croatian,
+        Language::Czech => InterfaceLanguage::Czech,
+        Language::Danish => InterfaceLanguage::Danish,
+        Language::Dutch => InterfaceLanguage::Dutch,
+        Language::English => InterfaceLanguage::English,
+        Language::Esperanto => InterfaceLanguage::Esperanto,
+        Language::Estonian => InterfaceLanguage::Estonian,
+        Language::Finnish => InterfaceLanguage::Finnish,
+        Language::French => InterfaceLanguage::French,
+        Language::Ganda => InterfaceLanguage::Ganda,
+        Language::Georgian => InterfaceLanguage::Georgian,
+        Language::German => InterfaceLanguage::German,
+        Language::Greek => InterfaceLanguage::Greek,
+        Language::Gujarati => InterfaceLanguage::Gujarati,
+        Language::Hebrew => InterfaceLanguage::Hebrew,
+        Language::Hindi => InterfaceLanguage::Hindi,
+        Language::Hungarian => InterfaceLanguage::Hungarian,
+        Language::Icelandic => InterfaceLanguage::Icelandic,
+        Language::Indonesian => InterfaceLanguage::Indonesian,
+        Language::Irish => InterfaceLanguage::Irish,
+        Language::Italian => InterfaceLanguage::Italian,
+        Language::Japanese => InterfaceLanguage::Japanese,
+        Language::Kazakh => InterfaceLanguage::Kazakh,
+        Language::Korean => InterfaceLanguage::Korean,
+        Language::Latin => InterfaceLanguage::Latin,
+        Language::Latvian => InterfaceLanguage::Latvian,
+        Language::Lithuanian => InterfaceLanguage::Lithuanian,
+        Language::Macedonian => InterfaceLanguage::Macedonian,
+        Language::Malay => InterfaceLanguage::Malay,
+        Language::Maori => InterfaceLanguage::Maori,
+        Language::Marathi => InterfaceLanguage::Marathi,
+        Language::Mongolian => InterfaceLanguage::Mongolian,
+        Language::Nynorsk => InterfaceLanguage::NorwegianNynorsk,
+        Language::Persian => InterfaceLanguage::Persian,
+        Language::Polish => InterfaceLanguage::Polish,
+        Language::Portuguese => InterfaceLanguage::Portuguese,
+        Language::Punjabi => InterfaceLanguage::Punjabi,
+        Language::Romanian => InterfaceLanguage::Romanian,
+        Language::Russian => InterfaceLanguage::Russian,
+        Language::Serbian => InterfaceLanguage::Serbian,
+        Language::Shona => InterfaceLanguage::Shona,
+        Language::Slovak => InterfaceLanguage::Slovak,
+        Language::Slovene => InterfaceLanguage::Slovenian,
+        Language::Somali => InterfaceLanguage::Somali,
+        Language::Sotho => InterfaceLanguage::SouthernSotho,
+        Language::Spanish => InterfaceLanguage::Spanish,
+        Language::Swahili => InterfaceLanguage::Swahili,
+        Language::Swedish => InterfaceLanguage::Swedish,
+        Language::Tagalog => InterfaceLanguage::Tagalog,
+        Language::Tamil => InterfaceLanguage::Tamil,
+        Language::Telugu => InterfaceLanguage::Telugu,
+        Language::Thai => InterfaceLanguage::Thai,
+        Language::Tsonga => InterfaceLanguage::Tsonga,
+        Language::Tswana => InterfaceLanguage::Tswana,
+        Language::Turkish => InterfaceLanguage::Turkish,
+        Language::Ukrainian => InterfaceLanguage::Ukrainian,
+        Language::Urdu => InterfaceLanguage::Urdu,
+        Language::Vietnamese => InterfaceLanguage::Vietnamese,
+        Language::Welsh => InterfaceLanguage::Welsh,
+        Language::Xhosa => InterfaceLanguage::Xhosa,
+        Language::Yoruba => InterfaceLanguage::Yoruba,
+        Language::Zulu => InterfaceLanguage::Zulu,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_ranked() {
+        let d = LinguaDetector::new();
+        let ranked = d.detect_ranked("Die Katze sitzt auf der Matte und schläft den ganzen Tag.");
+        assert_eq!(ranked[0].0, InterfaceLanguage::German);
+        assert!(ranked.windows(2).all(|v| v[0].1 >= v[1].1));
+        assert!(ranked.iter().map(|v| v.1).sum::<f64>() <= 1.0 + 1e-9);
+    }
+
+    #[test]
+    fn with_languages() {
+        const TEXT: &str = "Die Katze sitzt auf der Matte und schläft den ganzen Tag.";
+        let (d, unsupported) = LinguaDetector::with_languages(&[
+            InterfaceLanguage::German,
+            InterfaceLanguage::French,
+            InterfaceLanguage::Klingon,
+        ]);
+        assert_eq!(unsupported, vec![InterfaceLanguage::Klingon]);
+        assert_eq!(d.detect_language(TEXT), Some(InterfaceLanguage::German));
+
+        let (d, _) = LinguaDetector::with_languages(&[InterfaceLanguage::French]);
+        assert_eq!(d.detect_language(TEXT), Some(InterfaceLanguage::French));
+        assert_eq!(
+            d.detect_ranked(TEXT),
+            vec![(InterfaceLanguage::French, 1.0)]
+        );
+
+        let (d, unsupported) = LinguaDetector::with_languages(&[InterfaceLanguage::Klingon]);
+        assert_eq!(unsupported, vec![InterfaceLanguage::Klingon]);
+        assert_eq!(d.detect_language(TEXT), None);
+        assert!(d.detect_ranked(TEXT).is_empty());
     }
 }
@@ -0,0 +1,196 @@
+use std::future::Future;
+
+use crate::{TranslationListOutput, TranslationOutput};
+
+/// Sends a batch as one joined request for providers that only accept a single text.
+///
+/// Segments that contain the delimiter and whitespace only segments are never joined,
+/// and if the provider returns a different number of segments the affected batch is sent again one segment at a time.
+#[derive(Clone, Copy, Debug)]
+pub struct Batcher {
+    delimiter: &'static str,
+}
+
+impl Batcher {
+    /// Joins segments with `\n` and splits on `\n` or `\r\n`
+    pub const fn newline() -> Self {
+        Self { delimiter: "\n" }
+    }
+
+    /// Joins segments with `delimiter`.
+    /// When splitting whitespace inside and around the delimiter is accepted, since providers like to add some.
+    pub const fn delimiter(delimiter: &'static str) -> Self {
+        Self { delimiter }
+    }
+
+    /// Translates `query` with `translate`, which is called with joined text
+    pub async fn translate<F, Fut>(
+        &self,
+        query: &[String],
+        translate: F,
+    ) -> anyhow::Result<TranslationListOutput>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = anyhow::Result<TranslationOutput>>,
+    {
+        let mut text = query.to_vec();
+        let mut lang = None;
+        for run in self.runs(query) {
+            let joined = run
+                .iter()
+                .map(|&i| query[i].as_str())
+                .collect::<Vec<_>>()
+                .join(self.delimiter);
+            let trans = translate(joined).await?;
+            lang = lang.or(trans.lang);
+            if let [i] = run[..] {
+                text[i] = trans.text;
+                continue;
+            }
+            let parts = self.split(&trans.text);
+            if parts.len() == run.len() {
+                for (&i, part) in run.iter().zip(parts) {
+                    text[i] = part;
+                }
+                continue;
+            }
+            // alignment broke, fall back to one request per segment
+            for &i in &run {
+                let trans = translate(query[i].clone()).await?;
+                lang = lang.or(trans.lang);
+                text[i] = trans.text;
+            }
+        }
+        Ok(TranslationListOutput { text, lang })
+    }
+
+    /// Indices of the segments that are sent together.
+    /// Segments containing the delimiter get their own request, blank segments none at all.
+    fn runs(&self, query: &[String]) -> Vec<Vec<usize>> {
+        let mut runs = vec![];
+        let mut current = vec![];
+        for (i, q) in query.iter().enumerate() {
+            if q.trim().is_empty() {
+                continue;
+            }
+            if self.split(q).len() > 1 {
+                if !current.is_empty() {
+                    runs.push(std::mem::take(&mut current));
+                }
+                runs.push(vec![i]);
+            } else {
+                current.push(i);
+            }
+        }
+        if !current.is_empty() {
+            runs.push(current);
+        }
+        runs
+    }
+
+    fn split(&self, text: &str) -> Vec<String> {
+        if self.delimiter.trim().is_empty() {
+            return text
+                .trim_end_matches(['\r', '\n'])
+                .split(self.delimiter)
+                .map(|v| v.strip_suffix('\r').unwrap_or(v).to_owned())
+                .collect();
+        }
+        let mut parts = vec![];
+        let mut last = 0;
+        let mut i = 0;
+        while i < text.len() {
+            match self.match_delimiter(&text[i..]) {
+                Some(len) => {
+                    parts.push(text[last..i].trim().to_owned());
+                    i += len;
+                    last = i;
+                }
+                None => i += text[i..].chars().next().map_or(1, char::len_utf8),
+            }
+        }
+        parts.push(text[last..].trim().to_owned());
+        parts
+    }
+
+    /// Byte length of the delimiter at the start of `text`, allowing whitespace between its characters
+    fn match_delimiter(&self, text: &str) -> Option<usize> {
+        let mut chars = text.char_indices().peekable();
+        for (n, d) in self.delimiter.chars().enumerate() {
+            if n > 0 {
+                while chars
+                    .next_if(|(_, c)| c.is_whitespace() && *c != d)
+                    .is_some()
+                {}
+            }
+            chars.next_if(|(_, c)| *c == d)?;
+        }
+        Some(chars.peek().map_or(text.len(), |(i, _)| *i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::Language;
+
+    fn strings(v: &[&str]) -> Vec<String> {
+        v.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn lenient_split() {
+        let b = Batcher::delimiter("_._._");
+        assert_eq!(b.split("a _. _._ b_._._c"), strings(&["a", "b", "c"]));
+        assert_eq!(
+            Batcher::newline().split("a\r\nb\nc"),
+            strings(&["a", "b", "c"])
+        );
+    }
+
+    #[tokio::test]
+    async fn isolate_segments_with_delimiter() {
+        let requests = Mutex::new(vec![]);
+        let query = strings(&["one", "two\nlines", "", "three", "four"]);
+        let out = Batcher::newline()
+            .translate(&query, |q| {
+                requests.lock().unwrap().push(q.clone());
+                async move {
+                    Ok(TranslationOutput {
+                        text: q.to_uppercase(),
+                        lang: Some(Language::English),
+                    })
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            out.text,
+            strings(&["ONE", "TWO\nLINES", "", "THREE", "FOUR"])
+        );
+        assert_eq!(out.lang, Some(Language::English));
+        assert_eq!(
+            requests.into_inner().unwrap(),
+            strings(&["one", "two\nlines", "three\nfour"])
+        );
+    }
+
+    #[tokio::test]
+    async fn fall_back_on_misalignment() {
+        let requests = Mutex::new(0);
+        let query = strings(&["a", "b", "c"]);
+        let out = Batcher::delimiter("_._._")
+            .translate(&query, |q| {
+                *requests.lock().unwrap() += 1;
+                // provider merges the delimiter into the text
+                let text = q.replace("_._._", " ");
+                async move { Ok(TranslationOutput { text, lang: None }) }
+            })
+            .await
+            .unwrap();
+        assert_eq!(out.text, query);
+        assert_eq!(requests.into_inner().unwrap(), 4);
+    }
+}
@@ -0,0 +1,313 @@
+use std::ops::Range;
+
+use crate::segment::split_sentences;
+
+/// How a translator measures text length
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LengthUnit {
+    #[default]
+    Chars,
+    /// utf-8 bytes
+    Bytes,
+}
+
+impl LengthUnit {
+    pub fn len(&self, text: &str) -> usize {
+        match self {
+            LengthUnit::Chars => text.chars().count(),
+            LengthUnit::Bytes => text.len(),
+        }
+    }
+}
+
+/// Input limits of a translator, `None` means unlimited.
+/// Offline models measure in tokens, their limits are a conservative estimate in characters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub unit: LengthUnit,
+    /// Max length of a single segment
+    pub max_segment_len: Option<usize>,
+    /// Max combined length of all segments in one request
+    pub max_request_len: Option<usize>,
+    /// Max number of segments in one request
+    pub max_segments: Option<usize>,
+    /// Length every segment adds to the request, e.g. the delimiter of providers that join segments
+    pub overhead: usize,
+}
+
+impl Limits {
+    /// Only limits the length of single segments in characters
+    pub const fn segment(max: usize) -> Self {
+        Self {
+            unit: LengthUnit::Chars,
+            max_segment_len: Some(max),
+            max_request_len: None,
+            max_segments: None,
+            overhead: 0,
+        }
+    }
+
+    /// Limits that satisfy both, counted in bytes if either counts bytes since that is never shorter than chars
+    pub fn strictest(self, other: Self) -> Self {
+        let min = |a: Option<usize>, b: Option<usize>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        Self {
+            unit: match other.unit {
+                LengthUnit::Bytes => other.unit,
+                LengthUnit::Chars => self.unit,
+            },
+            max_segment_len: min(self.max_segment_len, other.max_segment_len),
+            max_request_len: min(self.max_request_len, other.max_request_len),
+            max_segments: min(self.max_segments, other.max_segments),
+            overhead: self.overhead.max(other.overhead),
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.max_segment_len.is_none()
+            && self.max_request_len.is_none()
+            && self.max_segments.is_none()
+    }
+
+    /// Longest piece that fits into a request on its own
+    fn max_piece_len(&self) -> Option<usize> {
+        let request = self
+            .max_request_len
+            .map(|v| v.saturating_sub(self.overhead).max(1));
+        match (self.max_segment_len, request) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// Segments split into pieces that fit the [`Limits`] and grouped into requests
+#[derive(Clone, Debug)]
+pub struct Chunks {
+    pieces: Vec<String>,
+    /// Whitespace that followed each piece in the original segment
+    separators: Vec<String>,
+    /// Segment every piece belongs to
+    owners: Vec<usize>,
+    segments: usize,
+    batches: Vec<Range<usize>>,
+}
+
+impl Chunks {
+    pub fn new(query: &[String], limits: &Limits) -> Self {
+        let mut pieces = vec![];
+        let mut separators = vec![];
+        let mut owners = vec![];
+        for (i, segment) in query.iter().enumerate() {
+            for (piece, separator) in split_segment(segment, limits) {
+                pieces.push(piece);
+                separators.push(separator);
+                owners.push(i);
+            }
+        }
+
+        let mut batches = vec![];
+        let mut start = 0;
+        let mut len = 0;
+        for (i, piece) in pieces.iter().enumerate() {
+            let piece_len = limits.unit.len(piece) + limits.overhead;
+            let full = limits.max_segments.is_some_and(|max| i - start >= max)
+                || limits
+                    .max_request_len
+                    .is_some_and(|max| len + piece_len > max);
+            if full && i > start {
+                batches.push(start..i);
+                start = i;
+                len = 0;
+            }
+            len += piece_len;
+        }
+        if start < pieces.len() {
+            batches.push(start..pieces.len());
+        }
+
+        Self {
+            pieces,
+            separators,
+            owners,
+            segments: query.len(),
+            batches,
+        }
+    }
+
+    pub fn pieces(&self) -> &[String] {
+        &self.pieces
+    }
+
+    /// Pieces to send per request
+    pub fn batches(&self) -> impl Iterator<Item = &[String]> {
+        self.batches.iter().map(|r| &self.pieces[r.clone()])
+    }
+
+    /// Joins the translated pieces back into one translation per segment.
+    /// Returns `None` if the number of pieces doesn't match.
+    pub fn stitch(&self, translated: Vec<String>) -> Option<Vec<String>> {
+        if translated.len() != self.pieces.len() {
+            return None;
+        }
+        let mut out = vec![String::new(); self.segments];
+        for ((text, separator), owner) in translated
+            .into_iter()
+            .zip(&self.separators)
+            .zip(&self.owners)
+        {
+            if separator.is_empty() {
+                out[*owner].push_str(&text);
+            } else {
+                out[*owner].push_str(text.trim_end());
+                out[*owner].push_str(separator);
+            }
+        }
+        Some(out)
+    }
+}
+
+/// Pieces of `segment` with the whitespace that followed them
+fn split_segment(segment: &str, limits: &Limits) -> Vec<(String, String)> {
+    let unit = limits.unit;
+    let Some(max) = limits
+        .max_piece_len()
+        .filter(|max| unit.len(segment) > *max)
+    else {
+        return vec![(segment.to_owned(), String::new())];
+    };
+
+    // sentences, then words, then characters, until every part fits
+    let mut parts = vec![];
+    for sentence in split_sentences(segment, None) {
+        if unit.len(sentence) <= max {
+            parts.push(sentence);
+            continue;
+        }
+        for word in sentence.split_inclusive(char::is_whitespace) {
+            if unit.len(word) <= max {
+                parts.push(word);
+                continue;
+            }
+            let mut start = 0;
+            let mut len = 0;
+            for (i, c) in word.char_indices() {
+                let c_len = unit.len(c.encode_utf8(&mut [0; 4]));
+                if len + c_len > max && i > start {
+                    parts.push(&word[start..i]);
+                    start = i;
+                    len = 0;
+                }
+                len += c_len;
+            }
+            parts.push(&word[start..]);
+        }
+    }
+
+    let mut pieces: Vec<String> = vec![];
+    let mut current = String::new();
+    for part in parts {
+        if !current.is_empty() && unit.len(&current) + unit.len(part) > max {
+            pieces.push(std::mem::take(&mut current));
+        }
+        current.push_str(part);
+    }
+    pieces.push(current);
+
+    pieces
+        .into_iter()
+        .map(|piece| {
+            let trimmed = piece.trim_end().len();
+            if trimmed == 0 {
+                return (piece, String::new());
+            }
+            let separator = piece[trimmed..].to_owned();
+            let mut piece = piece;
+            piece.truncate(trimmed);
+            (piece, separator)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(v: &[&str]) -> Vec<String> {
+        v.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn split_at_sentences() {
+        let limits = Limits {
+            max_segment_len: Some(30),
+            ..Default::default()
+        };
+        let query = strings(&["First sentence here. Second one is here. Third.", "short"]);
+        let chunks = Chunks::new(&query, &limits);
+        assert_eq!(
+            chunks.pieces(),
+            strings(&[
+                "First sentence here.",
+                "Second one is here. Third.",
+                "short"
+            ])
+        );
+        let upper = chunks.pieces().iter().map(|v| v.to_uppercase()).collect();
+        assert_eq!(
+            chunks.stitch(upper).unwrap(),
+            strings(&["FIRST SENTENCE HERE. SECOND ONE IS HERE. THIRD.", "SHORT"])
+        );
+    }
+
+    #[test]
+    fn split_cjk_and_long_words() {
+        let limits = Limits {
+            unit: LengthUnit::Bytes,
+            max_segment_len: Some(12),
+            ..Default::default()
+        };
+        let chunks = Chunks::new(
+            &strings(&["こんにちは。元気ですか？", "abcdefghijklmnop"]),
+            &limits,
+        );
+        assert_eq!(
+            chunks.pieces(),
+            strings(&[
+                "こんにち",
+                "は。",
+                "元気です",
+                "か？",
+                "abcdefghijkl",
+                "mnop"
+            ])
+        );
+        assert_eq!(
+            chunks.stitch(chunks.pieces().to_vec()).unwrap(),
+            strings(&["こんにちは。元気ですか？", "abcdefghijklmnop"])
+        );
+    }
+
+    #[test]
+    fn split_batches() {
+        let limits = Limits {
+            max_request_len: Some(10),
+            max_segments: Some(3),
+            overhead: 1,
+            ..Default::default()
+        };
+        let chunks = Chunks::new(&strings(&["a", "b", "c", "d", "eeeeeeee", "f"]), &limits);
+        let batches = chunks.batches().map(|v| v.to_vec()).collect::<Vec<_>>();
+        assert_eq!(
+            batches,
+            vec![
+                strings(&["a", "b", "c"]),
+                strings(&["d"]),
+                strings(&["eeeeeeee"]),
+                strings(&["f"])
+            ]
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use std::ops::Range;
+
+use crate::{Detector, Language};
+
+/// Turns raw detector scores into [`Detector::detect_ranked`](crate::Detector::detect_ranked) order:
+/// merges languages that appear twice, drops scores of zero or less,
+/// scales them down if they add up to more than 1 and sorts the most likely first.
+pub fn rank_scores(scores: impl IntoIterator<Item = (Language, f64)>) -> Vec<(Language, f64)> {
+    let mut out: Vec<(Language, f64)> = vec![];
+    for (lang, score) in scores {
+        if score.is_nan() || score <= 0.0 {
+            continue;
+        }
+        match out.iter_mut().find(|v| v.0 == lang) {
+            Some(v) => v.1 += score,
+            None => out.push((lang, score)),
+        }
+    }
+    let total = out.iter().map(|v| v.1).sum::<f64>();
+    if total > 1.0 {
+        out.iter_mut().for_each(|v| v.1 /= total);
+    }
+    out.sort_by(|a, b| b.1.total_cmp(&a.1));
+    out
+}
+
+/// Maps an allowlist into the native languages of a detector,
+/// returns them together with the entries the detector doesn't support.
+pub fn split_allowlist<T>(
+    allowlist: &[Language],
+    native: impl Fn(Language) -> Option<T>,
+) -> (Vec<T>, Vec<Language>) {
+    let mut supported = vec![];
+    let mut unsupported = vec![];
+    for lang in allowlist {
+        match native(*lang) {
+            Some(v) => supported.push(v),
+            None => unsupported.push(*lang),
+        }
+    }
+    (supported, unsupported)
+}
+
+/// Writing system of a character, as far as telling languages apart in mixed text needs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Script {
+    Latin,
+    /// Han and kana, Japanese mixes both
+    HanKana,
+    Hangul,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Thai,
+    Devanagari,
+    /// Letters of any other script
+    Other,
+}
+
+impl Script {
+    /// Script of `c`, `None` for characters without one like digits, punctuation and whitespace
+    pub fn of(c: char) -> Option<Self> {
+        if !c.is_alphabetic() {
+            return None;
+        }
+        Some(match c {
+            'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' | '\u{1E00}'..='\u{1EFF}' => {
+                Script::Latin
+            }
+            c if is_kana(c) || is_han(c) => Script::HanKana,
+            c if is_hangul(c) => Script::Hangul,
+            '\u{0400}'..='\u{052F}' => Script::Cyrillic,
+            '\u{0370}'..='\u{03FF}' | '\u{1F00}'..='\u{1FFF}' => Script::Greek,
+            '\u{0600}'..='\u{06FF}' | '\u{0750}'..='\u{077F}' | '\u{FB50}'..='\u{FEFF}' => {
+                Script::Arabic
+            }
+            '\u{0590}'..='\u{05FF}' => Script::Hebrew,
+            '\u{0E00}'..='\u{0E7F}' => Script::Thai,
+            '\u{0900}'..='\u{097F}' => Script::Devanagari,
+            _ => Script::Other,
+        })
+    }
+}
+
+/// Hiragana, katakana and half-width katakana
+pub fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{309F}' | '\u{30A0}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}')
+}
+
+/// Hangul syllables and jamo
+pub fn is_hangul(c: char) -> bool {
+    matches!(c, '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' | '\u{A960}'..='\u{A97F}' | '\u{AC00}'..='\u{D7AF}' | '\u{D7B0}'..='\u{D7FF}')
+}
+
+/// CJK ideographs and the iteration mark `々`
+pub fn is_han(c: char) -> bool {
+    matches!(c, '\u{3005}' | '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}' | '\u{20000}'..='\u{3134F}')
+}
+
+/// Splits `text` into byte ranges of one script each.
+/// Characters without a script stay with the run before them, leading ones with the first run.
+pub fn script_runs(text: &str) -> Vec<(Range<usize>, Script)> {
+    let mut out: Vec<(Range<usize>, Script)> = vec![];
+    for (i, c) in text.char_indices() {
+        let end = i + c.len_utf8();
+        match (Script::of(c), out.last_mut()) {
+            (Some(script), Some(last)) if last.1 != script => out.push((i..end, script)),
+            (_, Some(last)) => last.0.end = end,
+            (Some(script), None) => out.push((0..end, script)),
+            (None, None) => {}
+        }
+    }
+    out
+}
+
+/// Labels the script runs of `text` with `detector`, see [`Detector::detect_spans`].
+/// Runs the detector can't classify join the span before them and neighbours of the same language are merged.
+pub fn detect_script_runs<D: Detector + ?Sized>(
+    detector: &D,
+    text: &str,
+) -> Vec<(Range<usize>, Language)> {
+    let runs = script_runs(text)
+        .into_iter()
+        .map(|(range, _)| {
+            let lang = detector.detect_language(&text[range.clone()]);
+            (range, lang)
+        })
+        .collect();
+    merge_spans(runs)
+}
+
+/// Merges neighbouring spans of the same language, unlabeled spans join the one before them
+/// or the first labeled one if they lead.
+pub fn merge_spans(spans: Vec<(Range<usize>, Option<Language>)>) -> Vec<(Range<usize>, Language)> {
+    let mut out: Vec<(Range<usize>, Language)> = vec![];
+    let mut pending: Option<Range<usize>> = None;
+    for (range, lang) in spans {
+        match (lang, out.last_mut()) {
+            (Some(lang), Some(last)) if last.1 == lang => last.0.end = range.end,
+            (None, Some(last)) => last.0.end = range.end,
+            (Some(lang), _) => {
+                let start = pending.take().map_or(range.start, |v| v.start);
+                out.push((start..range.end, lang));
+            }
+            (None, None) => {
+                pending = Some(pending.map_or(range.clone(), |v| v.start..range.end));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rank_and_normalize() {
+        let ranked = rank_scores([
+            (Language::German, 0.5),
+            (Language::English, 3.0),
+            (Language::German, 0.5),
+            (Language::French, 0.0),
+            (Language::Dutch, f64::NAN),
+        ]);
+        assert_eq!(
+            ranked,
+            vec![(Language::English, 0.75), (Language::German, 0.25)]
+        );
+        assert_eq!(
+            rank_scores([(Language::German, 0.3)]),
+            vec![(Language::German, 0.3)]
+        );
+    }
+
+    #[test]
+    fn allowlist() {
+        let (supported, unsupported) = split_allowlist(
+            &[Language::Japanese, Language::Klingon, Language::English],
+            |v| v.to_639_1(),
+        );
+        assert_eq!(supported, vec!["ja", "en"]);
+        assert_eq!(unsupported, vec![Language::Klingon]);
+    }
+
+    /// Tells languages apart by script only
+    struct ByScript;
+
+    impl Detector for ByScript {
+        fn detect_language(&self, text: &str) -> Option<Language> {
+            match Script::of(text.chars().find(|c| c.is_alphabetic())?)? {
+                Script::HanKana => Some(Language::Japanese),
+                Script::Latin => Some(Language::English),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn runs() {
+        let text = "「今日はmeetingがある」 OK";
+        let runs = script_runs(text);
+        let parts = runs
+            .iter()
+            .map(|(range, script)| (&text[range.clone()], *script))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parts,
+            vec![
+                ("「今日は", Script::HanKana),
+                ("meeting", Script::Latin),
+                ("がある」 ", Script::HanKana),
+                ("OK", Script::Latin),
+            ]
+        );
+        assert!(script_runs("123 !?").is_empty());
+        // extended jamo
+        assert_eq!(Script::of('\u{A960}'), Some(Script::Hangul));
+        assert_eq!(Script::of('ｶ'), Some(Script::HanKana));
+    }
+
+    #[test]
+    fn spans() {
+        let text = "今日はmeetingがある";
+        let spans = detect_script_runs(&ByScript, text);
+        assert_eq!(
+            spans,
+            vec![
+                (0..9, Language::Japanese),
+                (9..16, Language::English),
+                (16..text.len(), Language::Japanese),
+            ]
+        );
+        // the hangul run can't be classified and joins the english span
+        assert_eq!(
+            detect_script_runs(&ByScript, "한국 hello world"),
+            vec![(0..18, Language::English)]
+        );
+    }
+}
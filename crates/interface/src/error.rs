@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use crate::Language;
 
 #[derive(Debug, thiserror::Error)]
@@ -20,6 +22,44 @@ pub enum Error {
     RequestFailed(u16),
     #[error("Translator required a input language")]
     NoLanguage,
+    #[error("Glossary terms were lost in translation")]
+    TermsLost(Vec<String>),
+    #[error("Placeholders went missing or were duplicated in translation")]
+    PlaceholderMismatch(Vec<String>),
+    #[error("Segments were translated into the wrong language")]
+    WrongLanguage(Vec<usize>),
+    #[error("Api is rate limiting requests")]
+    RateLimited(Option<Duration>),
+    #[error("Api rejected the credentials")]
+    Unauthorized,
+    #[error("Quota of the api key is used up")]
+    QuotaExceeded,
+    #[error("Translator keeps failing, skipped until it recovers")]
+    CircuitOpen,
+}
+
+/// Turns unsuccessful responses into errors, `429` into [`Error::RateLimited`] with the `Retry-After` delay.
+/// `401`/`403` become [`Error::Unauthorized`], `402` and DeepL's `456` [`Error::QuotaExceeded`].
+pub fn check_status(response: reqwest::Response) -> Result<reqwest::Response, Error> {
+    let status = response.status();
+    match status.as_u16() {
+        401 | 403 => return Err(Error::Unauthorized),
+        402 | 456 => return Err(Error::QuotaExceeded),
+        _ => {}
+    }
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        return Err(Error::RateLimited(retry_after));
+    }
+    if !status.is_success() {
+        return Err(Error::RequestFailed(status.as_u16()));
+    }
+    Ok(response)
 }
 
 #[derive(Debug)]
@@ -1,20 +1,114 @@
+pub mod batch;
+pub mod chunk;
+pub mod detect;
 pub mod error;
 pub mod prompt;
+pub mod segment;
 pub mod tokenizer;
 
-use crate::prompt::PromptBuilder;
+use crate::{chunk::Limits, prompt::PromptBuilder};
 use aio_translator_lang_generator::generate_language;
 pub use interface_model::Model;
 
 generate_language!();
 
+impl Language {
+    /// Parses a language tag like `en`, `en-US`, `zh-Hant` or `jpn` using the iso 639 columns
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag.trim().replace('_', "-").to_lowercase();
+        let mut parts = tag.split('-');
+        let primary = parts.next()?;
+        if primary == "zh" && parts.any(|v| matches!(v, "hant" | "tw" | "hk" | "mo")) {
+            return Some(Language::ChineseTraditional);
+        }
+        Language::from_639_1(primary)
+            .or_else(|| Language::from_639_2T(primary))
+            .or_else(|| Language::from_639_2B(primary))
+            .or_else(|| Language::from_639_3(primary))
+    }
+
+    /// Shortest language tag, inverse of [`Language::from_tag`]
+    pub fn to_tag(&self) -> Option<&'static str> {
+        match self {
+            Language::ChineseTraditional => Some("zh-Hant"),
+            _ => self
+                .to_639_1()
+                .or_else(|| self.to_639_2T())
+                .or_else(|| self.to_639_2B())
+                .or_else(|| self.to_639_3()),
+        }
+    }
+}
+
 pub trait Detector {
     fn detect_language(&self, text: &str) -> Option<Language>;
+
+    /// Candidate languages with confidences from 0 to 1, most likely first.
+    /// The confidences add up to at most 1, detectors without scores report their guess with 1.
+    fn detect_ranked(&self, text: &str) -> Vec<(Language, f64)> {
+        self.detect_language(text)
+            .map(|v| vec![(v, 1.0)])
+            .unwrap_or_default()
+    }
+
+    /// Most likely language, if its confidence reaches `min_confidence`
+    fn detect_confident(&self, text: &str, min_confidence: f64) -> Option<Language> {
+        self.detect_ranked(text)
+            .first()
+            .filter(|v| v.1 >= min_confidence)
+            .map(|v| v.0)
+    }
+
+    /// Byte ranges of `text` with their language in order, text the detector can't classify joins a neighbour.
+    /// By default each script run is detected on its own, see [`detect::detect_script_runs`].
+    fn detect_spans(&self, text: &str) -> Vec<(std::ops::Range<usize>, Language)> {
+        detect::detect_script_runs(self, text)
+    }
+}
+
+/// Detector that may need the network, e.g. the detection endpoints of translation apis.
+/// Every [`Detector`] is an AsyncDetector that never fails.
+#[async_trait::async_trait]
+pub trait AsyncDetector: Send + Sync {
+    /// Most likely language of `text`, `None` if it can't be told
+    async fn detect(&self, text: &str) -> anyhow::Result<Option<Language>>;
+
+    /// Same as [`Detector::detect_ranked`]
+    async fn rank(&self, text: &str) -> anyhow::Result<Vec<(Language, f64)>> {
+        Ok(self
+            .detect(text)
+            .await?
+            .map(|v| vec![(v, 1.0)])
+            .unwrap_or_default())
+    }
+}
+
+#[async_trait::async_trait]
+impl<D: Detector + Send + Sync> AsyncDetector for D {
+    async fn detect(&self, text: &str) -> anyhow::Result<Option<Language>> {
+        Ok(self.detect_language(text))
+    }
+
+    async fn rank(&self, text: &str) -> anyhow::Result<Vec<(Language, f64)>> {
+        Ok(self.detect_ranked(text))
+    }
 }
 
 #[async_trait::async_trait]
 pub trait AsyncTranslator: Send + Sync {
     fn local(&self) -> bool;
+
+    /// Input limits of the translator, unlimited by default
+    fn limits(&self) -> Limits {
+        Limits::default()
+    }
+
+    /// Whether the translator can translate from `from` to `to`, `None` meaning the source is detected.
+    /// Supports every pair by default.
+    fn supports(&self, _from: Option<Language>, _to: &Language) -> bool {
+        true
+    }
+
     async fn translate(
         &self,
         query: &str,
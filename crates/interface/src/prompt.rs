@@ -2,9 +2,12 @@ use std::collections::HashMap;
 
 type ContentBuilder =
     fn(from: &str, to: &str, queries: &[String], data: PromptData) -> Option<String>;
+#[derive(Clone)]
 pub struct PromptBuilder {
     pd: PromptData,
     msgs: Vec<Message>,
+    /// Source/target pairs shown to the model as few-shot examples
+    examples: Vec<(String, String)>,
 }
 
 impl PromptBuilder {
@@ -12,14 +15,51 @@ impl PromptBuilder {
         let mut msgs = vec![Message::chat_system_template()];
         msgs.extend(Message::chat_sample());
         msgs.push(Message::main());
-        Self { pd, msgs }
+        Self {
+            pd,
+            msgs,
+            examples: vec![],
+        }
+    }
+
+    /// Adds a source/target pair as few-shot example
+    pub fn add_example(&mut self, source: String, target: String) {
+        self.examples.push((source, target));
     }
 
+    pub fn with_examples(mut self, examples: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.examples.extend(examples);
+        self
+    }
+
+    pub fn examples(&self) -> &[(String, String)] {
+        &self.examples
+    }
+
+    /// Chat messages as `(role, content)`.
+    /// The few-shot examples go between the chat samples and the queries, formatted like the queries.
     pub fn build(&self, from: &str, to: &str, queries: &[String]) -> Vec<(String, String)> {
-        todo!()
+        let render = |msg: &Message, queries: &[String]| {
+            (msg.content_builder)(from, to, queries, self.pd.clone())
+                .map(|content| (msg.role.name().to_owned(), content))
+        };
+        let Some((main, head)) = self.msgs.split_last() else {
+            return vec![];
+        };
+        let mut out = head
+            .iter()
+            .filter_map(|msg| render(msg, queries))
+            .collect::<Vec<_>>();
+        for (source, target) in &self.examples {
+            out.extend(render(main, std::slice::from_ref(source)));
+            out.push((Role::Assistant.name().to_owned(), format!("<|1|>{target}")));
+        }
+        out.extend(render(main, queries));
+        out
     }
 }
 
+#[derive(Clone)]
 pub struct Message {
     role: Role,
     content_builder: ContentBuilder,
@@ -78,13 +118,75 @@ impl Message {
     }
 }
 
+#[derive(Clone)]
 pub struct PromptData {
     chat_system_template: String,
     chat_sample: HashMap<String, Vec<String>>,
 }
 
+impl PromptData {
+    /// - `chat_system_template`: system prompt, `{to_lang}` is replaced with the target language
+    /// - `chat_sample`: user and assistant sample messages per target language
+    pub fn new(chat_system_template: String, chat_sample: HashMap<String, Vec<String>>) -> Self {
+        Self {
+            chat_system_template,
+            chat_sample,
+        }
+    }
+}
+
+#[derive(Clone)]
 enum Role {
     System,
     User,
     Assistant,
 }
+
+impl Role {
+    fn name(&self) -> &'static str {
+        match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder() -> PromptBuilder {
+        let sample = HashMap::from([(
+            "English".to_owned(),
+            vec!["<|1|>こんにちは".to_owned(), "<|1|>Hello".to_owned()],
+        )]);
+        PromptBuilder::new(PromptData::new(
+            "Translate to {to_lang}.".to_owned(),
+            sample,
+        ))
+    }
+
+    #[test]
+    fn build_with_examples() {
+        let prompt = builder()
+            .with_examples([("猫".to_owned(), "cat".to_owned())])
+            .build("Japanese", "English", &["犬".to_owned()]);
+        let roles = prompt.iter().map(|v| v.0.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            roles,
+            ["system", "user", "assistant", "user", "assistant", "user"]
+        );
+        assert_eq!(prompt[0].1, "Translate to English.");
+        assert!(prompt[3].1.ends_with("\n<|1|>猫"));
+        assert_eq!(prompt[4].1, "<|1|>cat");
+        assert!(prompt[5].1.ends_with("\n<|1|>犬"));
+    }
+
+    #[test]
+    fn build_without_samples() {
+        let prompt = builder().build("Japanese", "German", &["a".to_owned(), "b".to_owned()]);
+        assert_eq!(prompt.len(), 2);
+        assert!(prompt[1].1.ends_with("<|1|>a\n<|2|>b"));
+    }
+}
@@ -0,0 +1,339 @@
+use crate::Language;
+
+/// Splits `text` into sentences.
+/// Concatenating the sentences gives back `text`, whitespace after a sentence stays with it.
+///
+/// - CJK, Indic, Burmese, Khmer and Arabic sentence marks always end a sentence
+/// - `.`, `!` and `?` only if followed by whitespace, `.` not after an abbreviation of `lang` or an initial
+/// - a space between Thai or Lao characters ends a sentence, those scripts don't put spaces between words
+/// - a line break always ends a sentence
+pub fn split_sentences(text: &str, lang: Option<Language>) -> Vec<&str> {
+    let chars = text.char_indices().collect::<Vec<_>>();
+    let char_at = |i: usize| chars.get(i).map(|v| v.1);
+    let offset = |i: usize| chars.get(i).map_or(text.len(), |v| v.0);
+
+    let mut out = vec![];
+    let mut start = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].1;
+        let mut end = i + 1;
+        let boundary = if c == '\n' {
+            true
+        } else if is_space_script(c) && char_at(i + 1).is_some_and(|n| n == ' ') {
+            let mut next = i + 1;
+            while char_at(next).is_some_and(|n| n == ' ') {
+                next += 1;
+            }
+            char_at(next).is_some_and(is_space_script)
+        } else if is_terminator(c) {
+            while char_at(end).is_some_and(is_terminator) {
+                end += 1;
+            }
+            while char_at(end).is_some_and(is_closing) {
+                end += 1;
+            }
+            let run = &text[chars[i].0..offset(end)];
+            if run.chars().any(is_full_terminator) {
+                true
+            } else {
+                char_at(end).is_none_or(char::is_whitespace)
+                    && !(run.starts_with('.')
+                        && !run.starts_with("..")
+                        && is_abbreviation(text, chars[i].0, lang, next_word(&chars, end)))
+            }
+        } else {
+            false
+        };
+        if !boundary {
+            i += 1;
+            continue;
+        }
+        while char_at(end).is_some_and(char::is_whitespace) {
+            end += 1;
+        }
+        out.push(&text[start..offset(end)]);
+        start = offset(end);
+        i = end;
+    }
+    if start < text.len() {
+        out.push(&text[start..]);
+    }
+    out
+}
+
+/// Whitespace between sentences in `lang`
+pub fn sentence_separator(lang: &Language) -> &'static str {
+    match lang {
+        Language::Chinese
+        | Language::ChineseTraditional
+        | Language::ClassicalChinese
+        | Language::YueChinese
+        | Language::Japanese => "",
+        _ => " ",
+    }
+}
+
+/// Joins sentences with the spacing of `lang`
+pub fn join_sentences<S: AsRef<str>>(sentences: &[S], lang: &Language) -> String {
+    sentences
+        .iter()
+        .map(|v| v.as_ref().trim())
+        .filter(|v| !v.is_empty())
+        .collect::<Vec<_>>()
+        .join(sentence_separator(lang))
+}
+
+/// Segments split into pieces of a few sentences, for models that translate sentence by sentence
+#[derive(Clone, Debug)]
+pub struct Segmented {
+    pieces: Vec<String>,
+    /// Line breaks that followed each piece
+    breaks: Vec<Option<String>>,
+    /// Number of pieces per segment
+    sizes: Vec<usize>,
+}
+
+impl Segmented {
+    /// Splits every segment into sentences and groups up to `per_piece` sentences into one piece
+    pub fn new(query: &[String], lang: Option<Language>, per_piece: usize) -> Self {
+        let per_piece = per_piece.max(1);
+        let mut pieces = vec![];
+        let mut breaks = vec![];
+        let mut sizes = vec![];
+        for segment in query {
+            let mut size = 0;
+            let mut current: Vec<&str> = vec![];
+            for sentence in split_sentences(segment, lang) {
+                let line_break = sentence.trim_start().contains('\n');
+                current.push(sentence);
+                if current.len() == per_piece || line_break {
+                    let piece = current.concat();
+                    let trimmed = piece.trim();
+                    if !trimmed.is_empty() {
+                        pieces.push(trimmed.to_owned());
+                        breaks.push(line_break.then(|| line_breaks(&piece)));
+                        size += 1;
+                    } else if let Some(Some(last)) = breaks.last_mut().filter(|_| size > 0) {
+                        // empty lines between paragraphs
+                        last.push_str(&line_breaks(&piece));
+                    }
+                    current.clear();
+                }
+            }
+            let piece = current.concat();
+            if !piece.trim().is_empty() || size == 0 {
+                pieces.push(piece.trim().to_owned());
+                breaks.push(None);
+                size += 1;
+            }
+            sizes.push(size);
+        }
+        Self {
+            pieces,
+            breaks,
+            sizes,
+        }
+    }
+
+    pub fn pieces(&self) -> &[String] {
+        &self.pieces
+    }
+
+    pub fn pieces_mut(&mut self) -> &mut [String] {
+        &mut self.pieces
+    }
+
+    /// Rejoins the translated pieces into one translation per segment with the spacing of `to`
+    pub fn join(&self, translated: Vec<String>, to: &Language) -> Vec<String> {
+        let mut translated = translated.into_iter().zip(&self.breaks);
+        self.sizes
+            .iter()
+            .map(|size| {
+                let mut out = String::new();
+                for (n, (text, line_break)) in translated.by_ref().take(*size).enumerate() {
+                    out.push_str(text.trim());
+                    if n + 1 == *size {
+                        break;
+                    }
+                    match line_break {
+                        Some(v) => out.push_str(v),
+                        None => out.push_str(sentence_separator(to)),
+                    }
+                }
+                out
+            })
+            .collect()
+    }
+}
+
+fn line_breaks(text: &str) -> String {
+    text.chars().filter(|c| *c == '\n').collect()
+}
+
+fn is_terminator(c: char) -> bool {
+    matches!(c, '.' | '!' | '?') || is_full_terminator(c)
+}
+
+/// Marks that end a sentence without following whitespace
+fn is_full_terminator(c: char) -> bool {
+    matches!(
+        c,
+        '。' | '！' | '？' | '‥' | '…' | '｡' | '।' | '॥' | '။' | '។' | '؟' | '۔'
+    )
+}
+
+fn is_closing(c: char) -> bool {
+    matches!(
+        c,
+        '"' | '\'' | '”' | '’' | '»' | ')' | ']' | '」' | '』' | '）' | '】' | '〉' | '》'
+    )
+}
+
+/// Thai and Lao
+fn is_space_script(c: char) -> bool {
+    matches!(c, '\u{0E00}'..='\u{0EFF}')
+}
+
+fn next_word(chars: &[(usize, char)], mut i: usize) -> Option<char> {
+    while chars.get(i).is_some_and(|v| v.1.is_whitespace()) {
+        i += 1;
+    }
+    chars.get(i).map(|v| v.1)
+}
+
+/// Whether the `.` at `dot` belongs to an abbreviation instead of ending the sentence
+fn is_abbreviation(text: &str, dot: usize, lang: Option<Language>, next: Option<char>) -> bool {
+    if next.is_some_and(char::is_lowercase) {
+        return true;
+    }
+    let word = text[..dot]
+        .rsplit(|c: char| c.is_whitespace() || is_closing(c) || c == '(')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let letters = word.chars().filter(|c| c.is_alphabetic()).count();
+    // initials like `J. R. R. Tolkien`
+    if letters == 1 && word.chars().all(|c| c.is_alphabetic() || c == '.') {
+        return true;
+    }
+    // german ordinals like `3. Oktober`
+    if lang == Some(Language::German)
+        && !word.is_empty()
+        && word.chars().all(|c| c.is_ascii_digit())
+    {
+        return true;
+    }
+    // `No. 5`, while `the answer is no.` ends the sentence
+    if word == "no" && next.is_some_and(|c| c.is_ascii_digit()) {
+        return true;
+    }
+    abbreviations(lang).contains(&word.as_str())
+}
+
+fn abbreviations(lang: Option<Language>) -> &'static [&'static str] {
+    const EN: &[&str] = &[
+        "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "mt", "vs", "etc", "e.g", "i.e", "inc",
+        "ltd", "co", "fig", "approx", "dept", "jan", "feb", "mar", "apr", "jun", "jul", "aug",
+        "sep", "sept", "oct", "nov", "dec",
+    ];
+    const DE: &[&str] = &[
+        "z.b", "bzw", "usw", "ca", "nr", "dr", "prof", "hr", "fr", "vgl", "evtl", "ggf", "u.a",
+        "d.h", "str", "bspw", "inkl", "etc",
+    ];
+    const FR: &[&str] = &[
+        "m", "mme", "mlle", "dr", "pr", "etc", "p.ex", "av", "bd", "cf",
+    ];
+    const ES: &[&str] = &[
+        "sr", "sra", "srta", "dr", "dra", "ud", "uds", "etc", "p.ej", "pág", "núm",
+    ];
+    const IT: &[&str] = &["sig", "sig.ra", "dott", "prof", "ecc", "pag", "es"];
+    const PT: &[&str] = &["sr", "sra", "dr", "dra", "etc", "pág", "núm"];
+    const NL: &[&str] = &["dhr", "mevr", "bijv", "enz", "o.a", "d.w.z", "dr", "prof"];
+    const ALL: &[&str] = &[
+        "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "mt", "vs", "etc", "e.g", "i.e", "inc",
+        "ltd", "co", "fig", "approx", "dept", "z.b", "bzw", "usw", "ca", "nr", "vgl", "evtl",
+        "ggf", "u.a", "d.h", "mme", "mlle", "p.ex", "cf", "sra", "srta", "dra", "ud", "uds",
+        "p.ej", "sig", "dott", "ecc", "dhr", "mevr", "bijv", "enz", "d.w.z",
+    ];
+    match lang {
+        Some(Language::English) => EN,
+        Some(Language::German) => DE,
+        Some(Language::French) => FR,
+        Some(Language::Spanish) => ES,
+        Some(Language::Italian) => IT,
+        Some(Language::Portuguese) => PT,
+        Some(Language::Dutch) => NL,
+        _ => ALL,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latin_abbreviations() {
+        let text = "Mr. Smith met Dr. J. Doe at 5 p.m. today. It went well! Did it? Yes.";
+        assert_eq!(
+            split_sentences(text, Some(Language::English)),
+            vec![
+                "Mr. Smith met Dr. J. Doe at 5 p.m. today. ",
+                "It went well! ",
+                "Did it? ",
+                "Yes."
+            ]
+        );
+        assert_eq!(
+            split_sentences("Am 3. Oktober z.B. nicht. Gut.", Some(Language::German)),
+            vec!["Am 3. Oktober z.B. nicht. ", "Gut."]
+        );
+        assert_eq!(
+            split_sentences("Version 1.5 is out... Try it.", None),
+            vec!["Version 1.5 is out... ", "Try it."]
+        );
+        assert_eq!(
+            split_sentences("The answer is no. We left. See No. 5.", None),
+            vec!["The answer is no. ", "We left. ", "See No. 5."]
+        );
+    }
+
+    #[test]
+    fn cjk_and_thai() {
+        assert_eq!(
+            split_sentences(
+                "「こんにちは。」元気ですか？はい…",
+                Some(Language::Japanese)
+            ),
+            vec!["「こんにちは。」", "元気ですか？", "はい…"]
+        );
+        assert_eq!(
+            split_sentences("ฉันชอบกินข้าว วันนี้อากาศดี", Some(Language::Thai)),
+            vec!["ฉันชอบกินข้าว ", "วันนี้อากาศดี"]
+        );
+    }
+
+    #[test]
+    fn segment_and_join() {
+        let query = vec![
+            "一つ目。二つ目。三つ目。\n\n四つ目。".to_owned(),
+            String::new(),
+        ];
+        let segmented = Segmented::new(&query, Some(Language::Japanese), 2);
+        assert_eq!(
+            segmented.pieces(),
+            ["一つ目。二つ目。", "三つ目。", "四つ目。", ""]
+        );
+        let translated = ["One. Two.", "Three.", "Four.", ""]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(
+            segmented.join(translated, &Language::English),
+            vec!["One. Two. Three.\n\nFour.", ""]
+        );
+        assert_eq!(
+            join_sentences(&["一つ目。", "二つ目。"], &Language::Japanese),
+            "一つ目。二つ目。"
+        );
+    }
+}
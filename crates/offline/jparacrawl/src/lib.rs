@@ -2,8 +2,10 @@ use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 use aio_translator_interface::{
     AsyncTranslator, Language, Model, TranslationListOutput, TranslationOutput,
+    chunk::Limits,
     error::{self},
     prompt::PromptBuilder,
+    segment::Segmented,
     tokenizer::SentenceTokenizer,
 };
 use anyhow::bail;
@@ -80,6 +82,19 @@ impl AsyncTranslator for JParaCrawlTranslator {
     fn local(&self) -> bool {
         true
     }
+
+    fn limits(&self) -> Limits {
+        Limits::segment(256)
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        matches!(
+            (from, to),
+            (Some(Language::English), Language::Japanese)
+                | (Some(Language::Japanese), Language::English)
+        )
+    }
+
     async fn translate(
         &self,
         query: &str,
@@ -111,6 +126,8 @@ impl AsyncTranslator for JParaCrawlTranslator {
                 false
             }
         };
+        let segmented = Segmented::new(query, from, 1);
+        let target = *to;
         let (from, to) = match eng_src {
             true => ("en", "ja"),
             false => ("ja", "en"),
@@ -133,7 +150,7 @@ impl AsyncTranslator for JParaCrawlTranslator {
             .get(&model_name)
             .expect("loaded in function")
             .translate_batch(
-                query,
+                segmented.pieces(),
                 &TranslationOptions {
                     batch_type: BatchType::Examples,
                     beam_size: 5,
@@ -148,7 +165,7 @@ impl AsyncTranslator for JParaCrawlTranslator {
             )?;
 
         Ok(TranslationListOutput {
-            text: trans.into_iter().map(|v| v.0).collect(),
+            text: segmented.join(trans.into_iter().map(|v| v.0).collect(), &target),
             lang: None,
         })
     }
@@ -1,8 +1,8 @@
 use std::sync::{Arc, Mutex};
 
 use aio_translator_interface::{
-    AsyncTranslator, Language, Model, TranslationListOutput, TranslationOutput, error::Error,
-    prompt::PromptBuilder, tokenizer::SentenceTokenizer,
+    AsyncTranslator, Language, Model, TranslationListOutput, TranslationOutput, chunk::Limits,
+    error::Error, prompt::PromptBuilder, segment::Segmented, tokenizer::SentenceTokenizer,
 };
 use ct2rs::{BatchType, ComputeType, Config, Device, Tokenizer, TranslationOptions};
 
@@ -58,6 +58,15 @@ impl AsyncTranslator for MBart50Translator {
     fn local(&self) -> bool {
         true
     }
+
+    fn limits(&self) -> Limits {
+        Limits::segment(256)
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        from.is_some_and(|v| v.to_mbart_50().is_some()) && to.to_mbart_50().is_some()
+    }
+
     async fn translate(
         &self,
         query: &str,
@@ -82,13 +91,15 @@ impl AsyncTranslator for MBart50Translator {
         to: &Language,
     ) -> anyhow::Result<TranslationListOutput> {
         let from = from.ok_or(Error::NoLanguage)?;
+        let segmented = Segmented::new(query, Some(from), 1);
+        let target = *to;
         let from = from.to_mbart_50().ok_or(Error::UnknownLanguage(from))?;
         let to = to.to_mbart_50().ok_or(Error::UnknownLanguage(to.clone()))?;
         *self.from.lock().unwrap() = from.to_owned();
         let model = self.load().await?;
         let trans = model.translate_batch_with_target_prefix(
-            query,
-            &vec![vec![to.to_string()]; query.len()],
+            segmented.pieces(),
+            &vec![vec![to.to_string()]; segmented.pieces().len()],
             &TranslationOptions {
                 batch_type: BatchType::Examples,
                 repetition_penalty: 3.0,
@@ -101,7 +112,7 @@ impl AsyncTranslator for MBart50Translator {
             None,
         )?;
         Ok(TranslationListOutput {
-            text: trans.into_iter().map(|v| v.0).collect(),
+            text: segmented.join(trans.into_iter().map(|v| v.0).collect(), &target),
             lang: None,
         })
     }
@@ -1,7 +1,9 @@
 use aio_translator_interface::{
     AsyncTranslator, Language, Model, TranslationListOutput, TranslationOutput,
-    error::{self, Error},
+    chunk::Limits,
+    error::{self},
     prompt::PromptBuilder,
+    segment::Segmented,
     tokenizer::SentenceTokenizer,
 };
 use ct2rs::{BatchType, ComputeType, Config, Device, Tokenizer, TranslationOptions};
@@ -10,7 +12,6 @@ use interface_model::{
     ModelLoad, ModelRead, ModelSource, ModelWrap, impl_model_helpers, impl_model_load_helpers,
 };
 use maplit::hashmap;
-use regex::Regex;
 
 pub struct SugoiTranslator {
     loaded_models: ModelWrap<ct2rs::Translator<MyTokenizer>>,
@@ -18,62 +19,6 @@ pub struct SugoiTranslator {
     compute_type: ComputeType,
 }
 
-fn split_sentences(q: &str, re: &Regex) -> Vec<String> {
-    let mut result = Vec::new();
-    let mut last = 0;
-
-    for mat in re.find_iter(q) {
-        let start = mat.start();
-        let end = mat.end();
-        if last < start {
-            result.push(q[last..start].to_string());
-        }
-        result.push(q[start..end].to_string());
-        last = end;
-    }
-
-    if last < q.len() {
-        result.push(q[last..].to_string());
-    }
-
-    result
-}
-fn tokenize(queries: &[String]) -> (Vec<String>, Vec<usize>) {
-    let mut new_queries: Vec<String> = vec![];
-    let mut query_split_sizes: Vec<usize> = vec![];
-    let re2 = Regex::new(r"[.。]").unwrap();
-
-    let re = Regex::new(r"(\w[.‥…!?。・]+)").unwrap();
-
-    for q in queries {
-        let sentences = split_sentences(&q, &re);
-        let mut chunk_queries = vec![];
-        for chunk in sentences.chunks(4) {
-            let s = chunk.concat();
-            let replaced = re2.replace_all(&s, "@").to_string();
-            chunk_queries.push(replaced);
-        }
-        query_split_sizes.push(chunk_queries.len());
-        new_queries.extend(chunk_queries);
-    }
-    (new_queries, query_split_sizes)
-}
-
-fn detokenize(queries: Vec<String>, query_split_sizes: Vec<usize>) -> Vec<String> {
-    let mut new_translations = vec![];
-    let mut i = 0;
-    for query_count in query_split_sizes {
-        let sentences = &queries[i..i + query_count].join(" ");
-        i += query_count;
-        let sentences = sentences
-            .replace('@', ".")
-            .replace('▁', " ")
-            .replace("<unk>", "");
-        new_translations.push(sentences);
-    }
-    new_translations
-}
-
 impl SugoiTranslator {
     /// single_loaded will only allow one model to be loaded at a time.
     pub fn new(cuda: bool, compute_type: ComputeType) -> Self {
@@ -84,17 +29,21 @@ impl SugoiTranslator {
         }
     }
 
-    fn pre_tokenize(&self, queries: &[String]) -> Result<(Vec<String>, Vec<usize>), Error> {
-        let (queries, query_split_sizes) = tokenize(queries);
-        Ok((queries, query_split_sizes))
+    /// Groups of 4 sentences, the model turns `.` into `@` so it is replaced up front
+    fn pre_tokenize(&self, queries: &[String]) -> Segmented {
+        let mut segmented = Segmented::new(queries, Some(Language::Japanese), 4);
+        for piece in segmented.pieces_mut() {
+            *piece = piece.replace(['.', '。'], "@");
+        }
+        segmented
     }
 
-    fn post_detokenize(
-        &self,
-        sentences: Vec<String>,
-        query_split_sizes: Vec<usize>,
-    ) -> anyhow::Result<Vec<String>> {
-        Ok(detokenize(sentences, query_split_sizes))
+    fn post_detokenize(&self, segmented: &Segmented, sentences: Vec<String>) -> Vec<String> {
+        let sentences = sentences
+            .into_iter()
+            .map(|v| v.replace('@', ".").replace('▁', " ").replace("<unk>", ""))
+            .collect();
+        segmented.join(sentences, &Language::English)
     }
 }
 
@@ -103,6 +52,15 @@ impl AsyncTranslator for SugoiTranslator {
     fn local(&self) -> bool {
         true
     }
+
+    fn limits(&self) -> Limits {
+        Limits::segment(256)
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        from == Some(Language::Japanese) && *to == Language::English
+    }
+
     async fn translate(
         &self,
         query: &str,
@@ -131,10 +89,10 @@ impl AsyncTranslator for SugoiTranslator {
             Err(error::Error::UnknownLanguageGroup(from, to.clone()))?;
         };
 
-        let (query, query_split_sizes) = self.pre_tokenize(query)?;
+        let segmented = self.pre_tokenize(query);
         let model = self.load().await?;
         let trans = model.translate_batch(
-            &query,
+            segmented.pieces(),
             &TranslationOptions {
                 batch_type: BatchType::Examples,
                 beam_size: 5,
@@ -148,8 +106,7 @@ impl AsyncTranslator for SugoiTranslator {
             None,
         )?;
         Ok(TranslationListOutput {
-            text: self
-                .post_detokenize(trans.into_iter().map(|v| v.0).collect(), query_split_sizes)?,
+            text: self.post_detokenize(&segmented, trans.into_iter().map(|v| v.0).collect()),
             lang: None,
         })
     }
@@ -0,0 +1,124 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aio_translator_interface::error::{Error, check_status};
+use aio_translator_interface::{AsyncDetector, Language};
+use reqwest::Client;
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+
+use crate::{get_auth_ppg, version_key};
+
+/// Detector using the language detection of the Papago website
+pub struct PapagoDetector {
+    client: Client,
+    ver: String,
+    url: String,
+}
+
+impl PapagoDetector {
+    pub async fn new() -> Result<PapagoDetector, Error> {
+        let client = Client::new();
+        let ver = version_key(&client).await?;
+
+        Ok(PapagoDetector {
+            client,
+            ver,
+            url: "https://papago.naver.com/apis/langs/dect".to_owned(),
+        })
+    }
+
+    /// Send requests to `url` instead, e.g. a proxy
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncDetector for PapagoDetector {
+    async fn detect(&self, text: &str) -> anyhow::Result<Option<Language>> {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let ppg = get_auth_ppg(&self.url, &self.ver, &uuid::Uuid::new_v4().to_string(), ts)?;
+        let content: Root1 = self
+            .client
+            .post(&self.url)
+            .header(AUTHORIZATION, ppg)
+            .header(
+                CONTENT_TYPE,
+                "application/x-www-form-urlencoded; charset=UTF-8",
+            )
+            .header("Timestamp", ts.to_string())
+            .form(&[("query", text)])
+            .send()
+            .await
+            .map_err(Error::from)
+            .and_then(check_status)?
+            .json()
+            .await?;
+        // `unk` is reported for text without a language
+        if content.lang_code == "unk" {
+            return Ok(None);
+        }
+        let lang = Language::from_papago(&content.lang_code)
+            .ok_or(Error::CouldNotMapLanguage(Some(content.lang_code)))?;
+        Ok(Some(lang))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Root1 {
+    #[serde(rename = "langCode")]
+    lang_code: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use aio_translator_interface::{AsyncDetector as _, Language};
+    use reqwest::Client;
+    use serde_json::json;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_string, header_exists, method},
+    };
+
+    use crate::PapagoDetector;
+
+    fn detector(url: String) -> PapagoDetector {
+        PapagoDetector {
+            client: Client::new(),
+            ver: "v1.8.0_dbc4af0f7c".to_owned(),
+            url,
+        }
+    }
+
+    #[tokio::test]
+    async fn detect() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header_exists("authorization"))
+            .and(header_exists("timestamp"))
+            .and(body_string("query=%EC%95%88%EB%85%95"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "langCode": "ko" })))
+            .mount(&server)
+            .await;
+
+        assert_eq!(
+            detector(server.uri()).detect("안녕").await.unwrap(),
+            Some(Language::Korean)
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "langCode": "unk" })))
+            .mount(&server)
+            .await;
+
+        assert_eq!(detector(server.uri()).detect("123").await.unwrap(), None);
+    }
+}
@@ -1,6 +1,8 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use aio_translator_interface::error::Error;
+use aio_translator_interface::batch::Batcher;
+use aio_translator_interface::chunk::Limits;
+use aio_translator_interface::error::{Error, check_status};
 use aio_translator_interface::prompt::PromptBuilder;
 use aio_translator_interface::{
     AsyncTranslator, Language, TranslationListOutput, TranslationOutput,
@@ -13,6 +15,10 @@ use reqwest::Client;
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
 use serde::{Deserialize, Serialize};
 
+mod detector;
+
+pub use detector::PapagoDetector;
+
 pub struct PapagoTranslator {
     client: Client,
     ver: String,
@@ -37,6 +43,19 @@ impl AsyncTranslator for PapagoTranslator {
     fn local(&self) -> bool {
         false
     }
+
+    fn limits(&self) -> Limits {
+        Limits {
+            max_request_len: Some(5000),
+            overhead: 1,
+            ..Default::default()
+        }
+    }
+
+    fn supports(&self, from: Option<Language>, to: &Language) -> bool {
+        from.is_none_or(|v| v.to_papago().is_some()) && to.to_papago().is_some()
+    }
+
     async fn translate(
         &self,
         query: &str,
@@ -71,7 +90,9 @@ impl AsyncTranslator for PapagoTranslator {
                 ("text", query),
             ])
             .send()
-            .await?
+            .await
+            .map_err(Error::from)
+            .and_then(check_status)?
             .json()
             .await?;
         let lang = content
@@ -99,15 +120,11 @@ impl AsyncTranslator for PapagoTranslator {
         from: Option<Language>,
         to: &Language,
     ) -> anyhow::Result<TranslationListOutput> {
-        let t = self.translate(&query.join("\n"), None, from, to).await?;
-        Ok(TranslationListOutput {
-            text: t
-                .text
-                .split("\n")
-                .map(|v| v.to_string())
-                .collect::<Vec<String>>(),
-            lang: t.lang,
-        })
+        Batcher::newline()
+            .translate(query, |q| async move {
+                self.translate(&q, None, from, to).await
+            })
+            .await
     }
 }
 